@@ -0,0 +1,328 @@
+use crate::{
+    engine::types::{BlockNumber, SpecId},
+    error::SoflError,
+};
+
+/// Hardfork schedule of a chain, used to select the `SpecId` to execute a block with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    /// Hardforks activated at a block number, sorted by block number.
+    pub block_forks: Vec<(BlockNumber, SpecId)>,
+    /// Hardforks activated at a timestamp after the merge, sorted by timestamp.
+    pub timestamp_forks: Vec<(u64, SpecId)>,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl ChainConfig {
+    pub fn mainnet() -> Self {
+        Self {
+            chain_id: 1,
+            block_forks: vec![
+                (0, SpecId::FRONTIER),
+                (200000, SpecId::FRONTIER_THAWING),
+                (1150000, SpecId::HOMESTEAD),
+                (1920000, SpecId::DAO_FORK),
+                (2463000, SpecId::TANGERINE),
+                (2675000, SpecId::SPURIOUS_DRAGON),
+                (4370000, SpecId::BYZANTIUM),
+                (7280000, SpecId::CONSTANTINOPLE),
+                (7280000, SpecId::PETERSBURG),
+                (9069000, SpecId::ISTANBUL),
+                (9200000, SpecId::MUIR_GLACIER),
+                (12244000, SpecId::BERLIN),
+                (12965000, SpecId::LONDON),
+                (13773000, SpecId::ARROW_GLACIER),
+                (15050000, SpecId::GRAY_GLACIER),
+                (15537394, SpecId::MERGE),
+            ],
+            timestamp_forks: vec![
+                (1681338455, SpecId::SHANGHAI),
+                (1710338135, SpecId::CANCUN),
+            ],
+        }
+    }
+
+    pub fn sepolia() -> Self {
+        Self {
+            chain_id: 11155111,
+            block_forks: vec![(0, SpecId::LONDON), (1450409, SpecId::MERGE)],
+            timestamp_forks: vec![
+                (1677557088, SpecId::SHANGHAI),
+                (1706655072, SpecId::CANCUN),
+            ],
+        }
+    }
+
+    pub fn holesky() -> Self {
+        Self {
+            chain_id: 17000,
+            block_forks: vec![(0, SpecId::MERGE)],
+            timestamp_forks: vec![
+                (1696000704, SpecId::SHANGHAI),
+                (1707305664, SpecId::CANCUN),
+            ],
+        }
+    }
+
+    /// The known chain with the chain id.
+    pub fn by_chain_id(chain_id: u64) -> Option<Self> {
+        [Self::mainnet(), Self::sepolia(), Self::holesky()]
+            .into_iter()
+            .find(|c| c.chain_id == chain_id)
+    }
+
+    /// Load a known chain by name (`mainnet`, `sepolia` or `holesky`),
+    /// or a custom chain from a genesis JSON file.
+    pub fn load(name_or_path: &str) -> Result<Self, SoflError> {
+        match name_or_path.to_lowercase().as_str() {
+            "mainnet" => Ok(Self::mainnet()),
+            "sepolia" => Ok(Self::sepolia()),
+            "holesky" => Ok(Self::holesky()),
+            _ => {
+                let json =
+                    std::fs::read_to_string(name_or_path).map_err(|e| {
+                        SoflError::Config(format!(
+                            "failed to read genesis file {}: {}",
+                            name_or_path, e
+                        ))
+                    })?;
+                Self::from_genesis_json(&json)
+            }
+        }
+    }
+
+    /// Parse the hardfork schedule in a geth genesis JSON, or in its `config` object.
+    /// The merge is activated at `mergeNetsplitBlock`, or at genesis if the terminal total
+    /// difficulty is 0. Otherwise, the merge block depends on the difficulty of the blocks
+    /// and is not scheduled: blocks before the first timestamp fork run with the last
+    /// pre-merge spec, unless `mergeNetsplitBlock` or a spec id is set.
+    /// The reth provider uses reth's own chain spec instead, which tracks the total difficulty.
+    pub fn from_genesis_json(json: &str) -> Result<Self, SoflError> {
+        let genesis: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| {
+                SoflError::Config(format!("invalid genesis json: {}", e))
+            })?;
+        let config = genesis.get("config").unwrap_or(&genesis);
+        let number = |key: &str| config.get(key).and_then(|v| v.as_u64());
+        let chain_id = number("chainId").ok_or(SoflError::Config(
+            "chainId not found in genesis json".to_string(),
+        ))?;
+
+        let mut block_forks = vec![(0, SpecId::FRONTIER)];
+        block_forks.extend(
+            [
+                ("homesteadBlock", SpecId::HOMESTEAD),
+                ("daoForkBlock", SpecId::DAO_FORK),
+                ("eip150Block", SpecId::TANGERINE),
+                ("eip158Block", SpecId::SPURIOUS_DRAGON),
+                ("byzantiumBlock", SpecId::BYZANTIUM),
+                ("constantinopleBlock", SpecId::CONSTANTINOPLE),
+                ("petersburgBlock", SpecId::PETERSBURG),
+                ("istanbulBlock", SpecId::ISTANBUL),
+                ("muirGlacierBlock", SpecId::MUIR_GLACIER),
+                ("berlinBlock", SpecId::BERLIN),
+                ("londonBlock", SpecId::LONDON),
+                ("arrowGlacierBlock", SpecId::ARROW_GLACIER),
+                ("grayGlacierBlock", SpecId::GRAY_GLACIER),
+                ("mergeNetsplitBlock", SpecId::MERGE),
+            ]
+            .into_iter()
+            .filter_map(|(key, spec)| number(key).map(|n| (n, spec))),
+        );
+        if number("mergeNetsplitBlock").is_none()
+            && number("terminalTotalDifficulty") == Some(0)
+        {
+            block_forks.push((0, SpecId::MERGE));
+        }
+        block_forks.sort_by_key(|(n, spec)| (*n, *spec as u8));
+
+        let mut timestamp_forks: Vec<_> = [
+            ("shanghaiTime", SpecId::SHANGHAI),
+            ("cancunTime", SpecId::CANCUN),
+        ]
+        .into_iter()
+        .filter_map(|(key, spec)| number(key).map(|t| (t, spec)))
+        .collect();
+        timestamp_forks.sort_by_key(|(t, spec)| (*t, *spec as u8));
+
+        Ok(Self {
+            chain_id,
+            block_forks,
+            timestamp_forks,
+        })
+    }
+
+    /// The spec of the latest hardfork activated at the block.
+    pub fn spec_at(&self, number: BlockNumber, timestamp: u64) -> SpecId {
+        let by_block = self
+            .block_forks
+            .iter()
+            .take_while(|(n, _)| *n <= number)
+            .last()
+            .map(|(_, spec)| *spec);
+        let by_timestamp = self
+            .timestamp_forks
+            .iter()
+            .take_while(|(t, _)| *t <= timestamp)
+            .last()
+            .map(|(_, spec)| *spec);
+        by_timestamp.or(by_block).unwrap_or(SpecId::FRONTIER)
+    }
+}
+
+/// Parse a hardfork name, e.g., `London`, `gray_glacier` or `paris`, case-insensitively.
+pub fn parse_spec_id(name: &str) -> Result<SpecId, SoflError> {
+    let spec = match name.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "frontier" => SpecId::FRONTIER,
+        "frontierthawing" => SpecId::FRONTIER_THAWING,
+        "homestead" => SpecId::HOMESTEAD,
+        "daofork" => SpecId::DAO_FORK,
+        "tangerine" | "tangerinewhistle" => SpecId::TANGERINE,
+        "spuriousdragon" => SpecId::SPURIOUS_DRAGON,
+        "byzantium" => SpecId::BYZANTIUM,
+        "constantinople" => SpecId::CONSTANTINOPLE,
+        "petersburg" => SpecId::PETERSBURG,
+        "istanbul" => SpecId::ISTANBUL,
+        "muirglacier" => SpecId::MUIR_GLACIER,
+        "berlin" => SpecId::BERLIN,
+        "london" => SpecId::LONDON,
+        "arrowglacier" => SpecId::ARROW_GLACIER,
+        "grayglacier" => SpecId::GRAY_GLACIER,
+        "merge" | "paris" => SpecId::MERGE,
+        "shanghai" => SpecId::SHANGHAI,
+        "cancun" => SpecId::CANCUN,
+        "latest" => SpecId::LATEST,
+        _ => {
+            return Err(SoflError::Config(format!("unknown hardfork {}", name)))
+        }
+    };
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{
+        transition::TransitionSpecBuilder,
+        types::{BlockEnv, SpecId, U256},
+    };
+
+    use super::{parse_spec_id, ChainConfig};
+
+    #[test]
+    fn test_mainnet_spec() {
+        let mainnet = ChainConfig::mainnet();
+        assert_eq!(mainnet.spec_at(0, 0), SpecId::FRONTIER);
+        assert_eq!(mainnet.spec_at(1919999, 0), SpecId::HOMESTEAD);
+        assert_eq!(mainnet.spec_at(7280000, 0), SpecId::PETERSBURG);
+        assert_eq!(mainnet.spec_at(15537393, 0), SpecId::GRAY_GLACIER);
+        assert_eq!(mainnet.spec_at(15537394, 1663224179), SpecId::MERGE);
+        assert_eq!(mainnet.spec_at(17034870, 1681338455), SpecId::SHANGHAI);
+        assert_eq!(mainnet.spec_at(19426587, 1710338135), SpecId::CANCUN);
+        assert_eq!(
+            ChainConfig::by_chain_id(17000),
+            Some(ChainConfig::holesky())
+        );
+        assert_eq!(ChainConfig::by_chain_id(5), None);
+    }
+
+    #[test]
+    fn test_genesis_json() {
+        let chain = ChainConfig::from_genesis_json(
+            r#"{
+                "config": {
+                    "chainId": 1337,
+                    "homesteadBlock": 0,
+                    "eip150Block": 0,
+                    "eip155Block": 0,
+                    "eip158Block": 0,
+                    "byzantiumBlock": 0,
+                    "constantinopleBlock": 0,
+                    "petersburgBlock": 0,
+                    "istanbulBlock": 0,
+                    "berlinBlock": 0,
+                    "londonBlock": 0,
+                    "terminalTotalDifficulty": 0,
+                    "shanghaiTime": 100
+                },
+                "alloc": {}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(chain.chain_id, 1337);
+        assert_eq!(chain.spec_at(5, 0), SpecId::MERGE);
+        assert_eq!(chain.spec_at(10, 99), SpecId::MERGE);
+        assert_eq!(chain.spec_at(10, 100), SpecId::SHANGHAI);
+
+        let chain = ChainConfig::from_genesis_json(
+            r#"{"chainId": 7, "londonBlock": 10}"#,
+        )
+        .unwrap();
+        assert_eq!(chain.spec_at(9, 0), SpecId::FRONTIER);
+        assert_eq!(chain.spec_at(10, 0), SpecId::LONDON);
+        assert!(ChainConfig::from_genesis_json("{}").is_err());
+
+        // the block reaching a non-zero terminal total difficulty is unknown
+        let chain = ChainConfig::from_genesis_json(
+            r#"{
+                "chainId": 7,
+                "londonBlock": 0,
+                "terminalTotalDifficulty": 100,
+                "shanghaiTime": 1000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(chain.spec_at(1_000_000, 999), SpecId::LONDON);
+        assert_eq!(chain.spec_at(1_000_000, 1000), SpecId::SHANGHAI);
+        let chain = ChainConfig::from_genesis_json(
+            r#"{
+                "chainId": 7,
+                "londonBlock": 0,
+                "terminalTotalDifficulty": 100,
+                "mergeNetsplitBlock": 50
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(chain.spec_at(49, 0), SpecId::LONDON);
+        assert_eq!(chain.spec_at(50, 0), SpecId::MERGE);
+    }
+
+    #[test]
+    fn test_parse_spec_id() {
+        assert_eq!(parse_spec_id("London").unwrap(), SpecId::LONDON);
+        assert_eq!(
+            parse_spec_id("gray_glacier").unwrap(),
+            SpecId::GRAY_GLACIER
+        );
+        assert_eq!(parse_spec_id("paris").unwrap(), SpecId::MERGE);
+        assert!(parse_spec_id("prague").is_err());
+    }
+
+    #[test]
+    fn test_transition_spec_with_chain() {
+        let block = BlockEnv {
+            number: U256::from(1800000),
+            timestamp: U256::from(1680000000),
+            ..Default::default()
+        };
+        let spec = TransitionSpecBuilder::new()
+            .set_block(block.clone())
+            .set_chain(ChainConfig::sepolia())
+            .build();
+        assert_eq!(spec.cfg.chain_id, 11155111);
+        assert_eq!(spec.cfg.spec_id, SpecId::SHANGHAI);
+
+        // counterfactual fork
+        let spec = TransitionSpecBuilder::new()
+            .set_block(block)
+            .set_chain(ChainConfig::sepolia())
+            .set_spec_id(SpecId::BERLIN)
+            .build();
+        assert_eq!(spec.cfg.spec_id, SpecId::BERLIN);
+    }
+}
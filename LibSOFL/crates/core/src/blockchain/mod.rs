@@ -1,3 +1,4 @@
+pub mod chain;
 pub mod provider;
 pub mod transaction;
 pub mod tx_position;
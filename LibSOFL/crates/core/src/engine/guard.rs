@@ -0,0 +1,329 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::error::SoflError;
+
+use super::{
+    inspector::EvmInspector,
+    state::BcState,
+    types::{
+        Address, Bytes, CallInputs, CreateInputs, Database, EVMData, Gas,
+        Inspector, InstructionResult, Interpreter, TxEnv,
+    },
+};
+
+/// The deadline is checked once every this many opcodes, as reading the clock is slow.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Why an execution is stopped by `ExecutionGuard`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, derive_more::Display)]
+pub enum Interruption {
+    #[display(fmt = "step budget exhausted")]
+    Steps,
+    #[display(fmt = "call depth exceeded")]
+    Depth,
+    #[display(fmt = "deadline reached")]
+    Timeout,
+    #[display(fmt = "cancelled")]
+    Cancelled,
+}
+
+/// Inspector stopping executions which run too many opcodes, nest calls too deep,
+/// reach a deadline or are cancelled through a shared flag.
+/// Once interrupted, every call frame halts after its current opcode, and the
+/// execution fails with `SoflError::Interrupted` without committing the interrupted transaction.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionGuard {
+    /// Maximum number of opcodes executed by each transaction.
+    max_steps: Option<u64>,
+    /// Maximum depth of call frames, 1 for the outermost one.
+    max_depth: Option<usize>,
+    deadline: Option<Instant>,
+    cancelled: Option<Arc<AtomicBool>>,
+    steps: u64,
+    depth: usize,
+    pub interruption: Option<Interruption>,
+}
+
+impl ExecutionGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline to the given time from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stop the execution once the flag is set, e.g., by a signal handler.
+    pub fn with_cancellation(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interruption.is_some()
+    }
+
+    /// Returns `SoflError::Interrupted` if the execution has been interrupted.
+    pub fn check(&self) -> Result<(), SoflError> {
+        match self.interruption {
+            Some(_) => Err(SoflError::Interrupted),
+            None => Ok(()),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    fn interrupt(&mut self, interruption: Interruption) {
+        self.interruption.get_or_insert(interruption);
+    }
+
+    /// Check the depth before entering a new call frame.
+    /// The frame is still entered, and halted at its first opcode,
+    /// so that the other inspectors see a `call_end` for each `call`.
+    fn enter(&mut self) {
+        self.depth += 1;
+        if self.max_depth.is_some_and(|max| self.depth > max) {
+            self.interrupt(Interruption::Depth);
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ExecutionGuard {
+    fn step(
+        &mut self,
+        _interp: &mut Interpreter<'_>,
+        _data: &mut EVMData<'_, DB>,
+    ) {
+        self.steps += 1;
+        if self.interruption.is_none() {
+            if self.max_steps.is_some_and(|max| self.steps > max) {
+                self.interrupt(Interruption::Steps);
+            } else if self.is_cancelled() {
+                self.interrupt(Interruption::Cancelled);
+            } else if self.steps % DEADLINE_CHECK_INTERVAL == 0
+                && self.deadline.is_some_and(|d| Instant::now() >= d)
+            {
+                self.interrupt(Interruption::Timeout);
+            }
+        }
+    }
+
+    /// Halt every call frame up to the outermost one.
+    /// This is done after the opcode, not before it in `step`,
+    /// so that the other inspectors see a `step_end` for each `step`.
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        _data: &mut EVMData<'_, DB>,
+    ) {
+        if self.is_interrupted() {
+            interp.instruction_result = InstructionResult::OutOfGas;
+        }
+    }
+
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.enter();
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.depth = self.depth.saturating_sub(1);
+        (ret, remaining_gas, out)
+    }
+
+    fn create(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.enter();
+        (InstructionResult::Continue, None, Gas::new(0), Bytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.depth = self.depth.saturating_sub(1);
+        (ret, address, remaining_gas, out)
+    }
+}
+
+impl<BS: BcState> EvmInspector<BS> for ExecutionGuard {
+    fn transaction(&mut self, _tx: &TxEnv, _state: &BS) -> bool {
+        self.steps = 0;
+        self.depth = 0;
+        if self.interruption.is_none() {
+            if self.is_cancelled() {
+                self.interrupt(Interruption::Cancelled);
+            } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                self.interrupt(Interruption::Timeout);
+            }
+        }
+        !self.is_interrupted()
+    }
+
+    fn interrupted(&self) -> bool {
+        self.is_interrupted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        conversion::ConvertTo,
+        engine::{
+            memory::{EmptyMemoryBcState, MemoryBcState},
+            state::{BcState, DatabaseRef},
+            transition::{TransitionSpec, TransitionSpecBuilder},
+            types::{AccountInfo, Address, Bytecode, TransactTo, TxEnv, U256},
+        },
+        error::SoflError,
+    };
+
+    use super::{ExecutionGuard, Interruption};
+
+    /// A contract writing a slot and then looping forever, and one calling itself recursively.
+    fn setup() -> (EmptyMemoryBcState, Address, Address) {
+        let mut state = MemoryBcState::fresh();
+        let looping: Address = 0x10.cvt();
+        let recursive: Address = 0x20.cvt();
+        let codes = [
+            // sstore(0, 1); jumpdest; jump(5)
+            (
+                looping,
+                vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x5b, 0x60, 0x05, 0x56],
+            ),
+            // pop(call(gas(), address(), 0, 0, 0, 0, 0))
+            (
+                recursive,
+                vec![
+                    0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                    0x30, 0x5a, 0xf1, 0x50, 0x00,
+                ],
+            ),
+        ];
+        for (address, code) in codes {
+            let bytecode = Bytecode::new_raw(code.into());
+            state.insert_account_info(
+                address,
+                AccountInfo {
+                    code_hash: bytecode.hash_slow(),
+                    code: Some(bytecode),
+                    ..Default::default()
+                },
+            );
+        }
+        (state, looping, recursive)
+    }
+
+    fn spec(to: Address, n: usize) -> TransitionSpec {
+        let mut builder = TransitionSpecBuilder::new().bypass_check();
+        for _ in 0..n {
+            let mut tx = TxEnv::default();
+            tx.transact_to = TransactTo::Call(to);
+            tx.gas_limit = 10_000_000_000;
+            builder = builder.append_tx_env(tx);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_step_budget() {
+        let (mut state, looping, _) = setup();
+        let mut guard = ExecutionGuard::new().with_max_steps(10_000);
+        let err = state.transit(spec(looping, 2), &mut guard).unwrap_err();
+        assert!(matches!(err, SoflError::Interrupted));
+        assert_eq!(guard.interruption, Some(Interruption::Steps));
+        assert!(matches!(guard.check(), Err(SoflError::Interrupted)));
+        // the interrupted transaction is not committed
+        assert_eq!(state.storage_ref(looping, U256::ZERO).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let (mut state, _, recursive) = setup();
+        let mut guard = ExecutionGuard::new().with_max_depth(8);
+        let err = state.transit(spec(recursive, 1), &mut guard).unwrap_err();
+        assert!(matches!(err, SoflError::Interrupted));
+        assert_eq!(guard.interruption, Some(Interruption::Depth));
+        assert!(guard.check().is_err());
+    }
+
+    #[test]
+    fn test_timeout_and_cancellation() {
+        let (mut state, looping, _) = setup();
+        let start = Instant::now();
+        let mut guard =
+            ExecutionGuard::new().with_timeout(Duration::from_millis(100));
+        assert!(state.transit(spec(looping, 1), &mut guard).is_err());
+        assert_eq!(guard.interruption, Some(Interruption::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            flag.store(true, Ordering::Relaxed);
+        });
+        let mut guard = ExecutionGuard::new().with_cancellation(cancelled);
+        assert!(state.transit(spec(looping, 1), &mut guard).is_err());
+        handle.join().unwrap();
+        assert_eq!(guard.interruption, Some(Interruption::Cancelled));
+
+        // nothing is executed once cancelled
+        let err = state.transit(spec(looping, 1), &mut guard).unwrap_err();
+        assert!(matches!(err, SoflError::Interrupted));
+        assert_eq!(state.storage_ref(looping, U256::ZERO).unwrap(), U256::ZERO);
+    }
+}
@@ -26,6 +26,12 @@ pub trait EvmInspector<BS: BcState>: revm::Inspector<BS> {
         _result: &ExecutionResult,
     ) {
     }
+
+    /// Whether the execution is interrupted, e.g., by `ExecutionGuard`.
+    /// The interrupted transaction is not committed and the execution fails with `SoflError::Interrupted`.
+    fn interrupted(&self) -> bool {
+        false
+    }
 }
 
 // /// Any inspector that implements `revm::Inspector` can be used as `EvmInspector`.
@@ -274,4 +280,8 @@ impl<'a, BS: BcState> EvmInspector<BS> for CombinedInspector<'a, BS> {
             i.transaction_end(_tx, _state, _result);
         });
     }
+
+    fn interrupted(&self) -> bool {
+        self.inspectors.iter().any(|i| i.interrupted())
+    }
 }
@@ -1,8 +1,12 @@
-use revm::db::CacheDB;
+use revm::db::{AccountState, CacheDB, DbAccount};
 
-use super::types::{AccountInfo, Address, Bytecode, Hash, StateChange, U256};
+use super::types::{
+    AccountInfo, Address, Bytecode, Hash, StateChange, KECCAK_EMPTY, U256,
+};
 
 /// In-memory BcState implementation, using revm's CacheDB.
+/// Changes committed to the state can be reverted with `snapshot` and `revert_to`,
+/// while changes made directly to the underlying CacheDB cannot.
 #[derive(
     Debug, Clone, derive_more::AsRef, derive_more::Deref, derive_more::DerefMut,
 )]
@@ -11,8 +15,37 @@ pub struct MemoryBcState<S: revm::DatabaseRef>(
     #[deref]
     #[deref_mut]
     revm::db::CacheDB<S>,
+    Journal,
 );
 
+/// Identifier of a snapshot of a `MemoryBcState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnapshotId(u64);
+
+/// How to undo a change committed to the cache.
+#[derive(Debug, Clone)]
+enum JournalEntry {
+    /// The account was not cached.
+    NewAccount(Address),
+    /// The whole account, whose storage is cleared by the change.
+    Account(Address, DbAccount),
+    /// The account info and state.
+    Info(Address, AccountInfo, AccountState),
+    /// The value of a storage slot, `None` if it was not cached.
+    Slot(Address, U256, Option<U256>),
+    /// The code with the hash was not cached.
+    NewCode(Hash),
+}
+
+/// Journal of committed changes, recorded only while there are snapshots.
+#[derive(Debug, Clone, Default)]
+struct Journal {
+    entries: Vec<JournalEntry>,
+    /// Snapshots taken, with the number of entries at that time, oldest first.
+    snapshots: Vec<(SnapshotId, usize)>,
+    next_id: u64,
+}
+
 impl<S: revm::DatabaseRef> revm::Database for MemoryBcState<S> {
     type Error = S::Error;
 
@@ -47,16 +80,155 @@ impl<S: revm::DatabaseRef> revm::Database for MemoryBcState<S> {
     }
 }
 
+impl<S: revm::DatabaseRef> revm::DatabaseRef for MemoryBcState<S> {
+    type Error = S::Error;
+
+    #[doc = " Get basic account information."]
+    fn basic_ref(
+        &self,
+        address: Address,
+    ) -> Result<Option<AccountInfo>, Self::Error> {
+        self.0.basic_ref(address)
+    }
+
+    #[doc = " Get account code by its hash."]
+    fn code_by_hash_ref(
+        &self,
+        code_hash: Hash,
+    ) -> Result<Bytecode, Self::Error> {
+        self.0.code_by_hash_ref(code_hash)
+    }
+
+    #[doc = " Get storage value of address at index."]
+    fn storage_ref(
+        &self,
+        address: Address,
+        index: U256,
+    ) -> Result<U256, Self::Error> {
+        self.0.storage_ref(address, index)
+    }
+
+    #[doc = " Get block hash by block number."]
+    fn block_hash_ref(&self, number: U256) -> Result<Hash, Self::Error> {
+        self.0.block_hash_ref(number)
+    }
+}
+
 impl<S: revm::DatabaseRef> revm::DatabaseCommit for MemoryBcState<S> {
     #[doc = " Commit changes to the database."]
     fn commit(&mut self, changes: StateChange) {
+        if !self.1.snapshots.is_empty() {
+            self.record(&changes);
+        }
         self.0.commit(changes)
     }
 }
 
 impl<S: revm::DatabaseRef> MemoryBcState<S> {
     pub fn new(state_ref: S) -> Self {
-        Self(revm::db::CacheDB::new(state_ref))
+        Self(revm::db::CacheDB::new(state_ref), Journal::default())
+    }
+
+    /// Take a snapshot of the state, which can be restored by `revert_to`.
+    /// Changes committed after the snapshot are journaled, instead of copying the state.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        let journal = &mut self.1;
+        let id = SnapshotId(journal.next_id);
+        journal.next_id += 1;
+        journal.snapshots.push((id, journal.entries.len()));
+        id
+    }
+
+    /// Revert the state to the snapshot, discarding the snapshot and those taken after it,
+    /// like `evm_revert` of anvil. Returns false if the snapshot does not exist.
+    pub fn revert_to(&mut self, id: SnapshotId) -> bool {
+        let journal = &mut self.1;
+        let Some(pos) = journal.snapshots.iter().position(|(s, _)| *s == id)
+        else {
+            return false;
+        };
+        let (_, len) = journal.snapshots[pos];
+        journal.snapshots.truncate(pos);
+        let accounts = &mut self.0.accounts;
+        let contracts = &mut self.0.contracts;
+        for entry in journal.entries.drain(len..).rev() {
+            match entry {
+                JournalEntry::NewAccount(address) => {
+                    accounts.remove(&address);
+                }
+                JournalEntry::Account(address, account) => {
+                    accounts.insert(address, account);
+                }
+                JournalEntry::Info(address, info, state) => {
+                    if let Some(account) = accounts.get_mut(&address) {
+                        account.info = info;
+                        account.account_state = state;
+                    }
+                }
+                JournalEntry::Slot(address, slot, value) => {
+                    if let Some(account) = accounts.get_mut(&address) {
+                        match value {
+                            Some(value) => account.storage.insert(slot, value),
+                            None => account.storage.remove(&slot),
+                        };
+                    }
+                }
+                JournalEntry::NewCode(hash) => {
+                    contracts.remove(&hash);
+                }
+            }
+        }
+        if journal.snapshots.is_empty() {
+            journal.entries.clear();
+        }
+        true
+    }
+
+    /// Record how to undo the changes before they are committed.
+    fn record(&mut self, changes: &StateChange) {
+        let entries = &mut self.1.entries;
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            // new code is cached by its hash, the same way as `CacheDB::insert_contract`
+            if let Some(code) =
+                account.info.code.as_ref().filter(|c| !c.is_empty())
+            {
+                let hash = if account.info.code_hash == KECCAK_EMPTY {
+                    code.hash_slow()
+                } else {
+                    account.info.code_hash
+                };
+                if !self.0.contracts.contains_key(&hash) {
+                    entries.push(JournalEntry::NewCode(hash));
+                }
+            }
+            let entry = match self.0.accounts.get(address) {
+                None => JournalEntry::NewAccount(*address),
+                // the storage is cleared by CacheDB
+                Some(cached)
+                    if account.is_selfdestructed() || account.is_created() =>
+                {
+                    JournalEntry::Account(*address, cached.clone())
+                }
+                Some(cached) => {
+                    for slot in account.storage.keys() {
+                        entries.push(JournalEntry::Slot(
+                            *address,
+                            *slot,
+                            cached.storage.get(slot).copied(),
+                        ));
+                    }
+                    JournalEntry::Info(
+                        *address,
+                        cached.info.clone(),
+                        cached.account_state.clone(),
+                    )
+                }
+            };
+            entries.push(entry);
+        }
     }
 }
 
@@ -79,17 +251,17 @@ impl<S: revm::DatabaseRef + Clone> MemoryBcState<S> {
 
 #[cfg(test)]
 mod tests {
-    use revm::Database;
+    use revm::{Database, DatabaseRef};
 
     use crate::{
         conversion::ConvertTo,
         engine::{
-            inspector::no_inspector,
-            memory::MemoryBcState,
-            state::BcState,
+            inspector::{no_inspector, NoInspector},
+            memory::{EmptyMemoryBcState, MemoryBcState},
+            state::{execute, BcState, BcStateRef, CommitPolicy},
             transition::TransitionSpecBuilder,
             types::{
-                AccountInfo, Address, BlockEnv, Bytecode, CfgEnv,
+                AccountInfo, Address, BlockEnv, Bytecode, CfgEnv, CreateScheme,
                 ExecutionResult, TransactTo, TxEnv, U256,
             },
         },
@@ -181,4 +353,199 @@ mod tests {
             "receiver balance should be increased by 500"
         );
     }
+
+    #[test]
+    fn test_snapshot_and_revert() {
+        let mut state = MemoryBcState::fresh();
+        let sender: Address = 1.cvt();
+        let counter: Address = 0x10.cvt();
+        // sstore(0, add(sload(0), 1))
+        let bytecode = Bytecode::new_raw(
+            vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00]
+                .into(),
+        );
+        state.insert_account_info(
+            counter,
+            AccountInfo {
+                code_hash: bytecode.hash_slow(),
+                code: Some(bytecode),
+                ..Default::default()
+            },
+        );
+        state
+            .insert_account_storage(counter, U256::from(1), U256::from(7))
+            .unwrap();
+        let inc = || {
+            let mut tx = TxEnv::default();
+            tx.caller = sender;
+            tx.transact_to = TransactTo::Call(counter);
+            tx.gas_limit = 100000;
+            TransitionSpecBuilder::new()
+                .bypass_check()
+                .append_tx_env(tx)
+                .build()
+        };
+        let slot = |state: &mut EmptyMemoryBcState, i: u64| {
+            state.storage(counter, U256::from(i)).unwrap()
+        };
+
+        state.transit(inc(), no_inspector()).unwrap();
+        let base = state.snapshot();
+
+        // transactions and direct writes after the snapshot
+        state.transit(inc(), no_inspector()).unwrap();
+        state
+            .insert_account_storage(counter, U256::from(1), U256::from(8))
+            .unwrap();
+        state.add_ether_balance(sender, U256::from(100)).unwrap();
+        let inner = state.snapshot();
+        let (changes, _) = state.simulate(inc(), no_inspector()).unwrap();
+        state.apply_changes(changes);
+        let other: Address = 0x20.cvt();
+        state.insert_account_info(
+            other,
+            AccountInfo {
+                balance: U256::from(5),
+                ..Default::default()
+            },
+        );
+        assert_eq!(slot(&mut state, 0), U256::from(3));
+
+        assert!(state.revert_to(inner));
+        assert_eq!(slot(&mut state, 0), U256::from(2));
+        assert_eq!(slot(&mut state, 1), U256::from(8));
+        assert_eq!(state.basic(other).unwrap(), None);
+        assert!(!state.revert_to(inner), "reverted snapshots are discarded");
+
+        assert!(state.revert_to(base));
+        assert_eq!(slot(&mut state, 0), U256::from(1));
+        assert_eq!(slot(&mut state, 1), U256::from(7));
+        let sender_info = state.basic(sender).unwrap().unwrap();
+        assert_eq!(sender_info.balance, U256::ZERO);
+        assert_eq!(sender_info.nonce, 1);
+        assert!(!state.revert_to(base));
+        assert!(state.1.entries.is_empty());
+
+        // explore alternative transaction sequences from the same state
+        for n in 1..4 {
+            let id = state.snapshot();
+            for _ in 0..n {
+                state.transit(inc(), no_inspector()).unwrap();
+            }
+            assert_eq!(slot(&mut state, 0), U256::from(1 + n));
+            assert!(state.revert_to(id));
+            assert_eq!(slot(&mut state, 0), U256::from(1));
+        }
+    }
+
+    #[test]
+    fn test_revert_deployed_code() {
+        let mut state = MemoryBcState::fresh();
+        let id = state.snapshot();
+        // mstore8(0, 0x2a); return(0, 1)
+        let mut tx = TxEnv::default();
+        tx.transact_to = TransactTo::Create(CreateScheme::Create);
+        tx.data =
+            vec![0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3]
+                .into();
+        tx.gas_limit = 100000;
+        let spec = TransitionSpecBuilder::new()
+            .bypass_check()
+            .append_tx_env(tx)
+            .build();
+        let result = state.transit(spec, no_inspector()).unwrap().remove(0);
+        let ExecutionResult::Success { output, .. } = result else {
+            panic!("deployment failed");
+        };
+        let deployed = output.address().copied().unwrap();
+        let hash = Bytecode::new_raw(vec![0x2a].into()).hash_slow();
+        assert_eq!(state.basic(deployed).unwrap().unwrap().code_hash, hash);
+        assert!(state.contracts.contains_key(&hash));
+
+        assert!(state.revert_to(id));
+        assert_eq!(state.basic(deployed).unwrap(), None);
+        assert!(!state.contracts.contains_key(&hash));
+    }
+
+    #[test]
+    fn test_commit_policies_and_simulate_ref() {
+        let mut state = MemoryBcState::fresh();
+        let counter: Address = 0x10.cvt();
+        // sstore(0, add(sload(0), 1))
+        let bytecode = Bytecode::new_raw(
+            vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00]
+                .into(),
+        );
+        state.insert_account_info(
+            counter,
+            AccountInfo {
+                code_hash: bytecode.hash_slow(),
+                code: Some(bytecode),
+                ..Default::default()
+            },
+        );
+        let incs = |n: u64| {
+            let mut builder = TransitionSpecBuilder::new().bypass_check();
+            for i in 0..n {
+                let mut tx = TxEnv::default();
+                tx.caller = Address::with_last_byte(i as u8 + 1);
+                tx.transact_to = TransactTo::Call(counter);
+                tx.gas_limit = 100000;
+                builder = builder.append_tx_env(tx);
+            }
+            builder.build()
+        };
+        let count = |state: &EmptyMemoryBcState| {
+            state.storage_ref(counter, U256::ZERO).unwrap()
+        };
+
+        // each transaction sees the changes of the previous ones
+        let (changes, results) = execute(
+            &mut state,
+            incs(2),
+            None::<NoInspector>,
+            CommitPolicy::EachTx,
+        )
+        .unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(results.len(), 2);
+        assert_eq!(count(&state), U256::from(2));
+
+        // all transactions see the original state
+        let (changes, _) = execute(
+            &mut state,
+            incs(2),
+            None::<NoInspector>,
+            CommitPolicy::Never,
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| {
+            c[&counter].storage[&U256::ZERO].present_value == U256::from(3)
+        }));
+        assert_eq!(count(&state), U256::from(2));
+
+        // concurrent simulations on the shared state
+        let shared = &state;
+        let simulated: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (1..=3)
+                .map(|n| {
+                    s.spawn(move || {
+                        let mut inspector = NoInspector {};
+                        shared.simulate_ref(incs(n), &mut inspector).unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for (n, (changes, results)) in simulated.into_iter().enumerate() {
+            assert_eq!(changes.len(), n + 1);
+            assert!(results
+                .iter()
+                .all(|r| matches!(r, ExecutionResult::Success { .. })));
+            let value = changes[0][&counter].storage[&U256::ZERO].present_value;
+            assert_eq!(value, U256::from(3));
+        }
+        assert_eq!(count(&state), U256::from(2));
+    }
 }
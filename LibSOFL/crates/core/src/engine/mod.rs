@@ -1,5 +1,8 @@
+pub mod guard;
 pub mod inspector;
 pub mod memory;
 pub mod state;
+pub mod state_diff;
+pub mod storage_access;
 pub mod transition;
 pub mod types;
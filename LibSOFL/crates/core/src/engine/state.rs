@@ -7,7 +7,8 @@ pub use revm::DatabaseRef;
 
 use super::types::Bytecode;
 use super::{
-    inspector::EvmInspector,
+    inspector::{EvmInspector, NoInspector},
+    memory::MemoryBcState,
     transition::TransitionSpec,
     types::{
         Account, AccountInfo, AccountStatus, Address, ExecutionResult,
@@ -25,61 +26,15 @@ pub trait BcState:
     fn transit<'a, I>(
         &'a mut self,
         spec: TransitionSpec,
-        mut inspector: &mut I,
+        inspector: &mut I,
     ) -> Result<Vec<ExecutionResult>, SoflError>
     where
         <Self as revm::Database>::Error: std::fmt::Debug,
         Self: 'a,
         I: EvmInspector<&'a mut Self>,
     {
-        let TransitionSpec { cfg, block, txs } = spec.into();
-        let mut evm = revm::EVM::new();
-        evm.env.cfg = cfg;
-        evm.env.block = block;
-        evm.database(self);
-        let mut results = Vec::new();
-        for tx in txs.into_iter() {
-            let insp = &mut inspector;
-            evm.env.tx = tx;
-
-            // inspector pre-transaction hook
-            if !insp.transaction(
-                &evm.env.tx,
-                evm.db
-                    .as_ref()
-                    .expect("impossible: db does not exists while database has been set in evm"),
-            ) {
-                // return false to skip transaction
-                results.push(revm::primitives::ExecutionResult::Halt {
-                    reason: revm::primitives::Halt::NotActivated,
-                    gas_used: 0,
-                });
-                continue;
-            }
-
-            // execute transaction
-            let result = evm.inspect_commit(insp).map_err(|e| match e {
-                revm::primitives::EVMError::Transaction(ee) => {
-                    SoflError::InvalidTransaction(ee)
-                }
-                revm::primitives::EVMError::Header(ee) => {
-                    SoflError::InvalidHeader(ee)
-                }
-                revm::primitives::EVMError::Database(ee) => {
-                    SoflError::BcState(format!("{:?}", ee))
-                }
-            })?;
-
-            // inspector post-transaction hook
-            (&mut inspector).transaction_end(
-                &evm.env.tx,
-                evm.db
-                    .as_ref()
-                    .expect("impossible: db does not exists while database has been set in evm"),
-                &result,
-            );
-            results.push(result);
-        }
+        let (_, results) =
+            execute(self, spec, Some(inspector), CommitPolicy::EachTx)?;
         Ok(results)
     }
 
@@ -92,97 +47,27 @@ pub trait BcState:
     where
         Self::Error: std::fmt::Debug,
     {
-        let TransitionSpec { cfg, block, txs } = spec;
-        let mut evm = revm::EVM::new();
-        evm.env.cfg = cfg;
-        evm.env.block = block;
-        evm.database(self);
-        let mut results = Vec::new();
-        for tx in txs.into_iter() {
-            evm.env.tx = tx;
-            let result = evm.transact_commit().map_err(|e| match e {
-                revm::primitives::EVMError::Transaction(ee) => {
-                    SoflError::InvalidTransaction(ee)
-                }
-                revm::primitives::EVMError::Header(ee) => {
-                    SoflError::InvalidHeader(ee)
-                }
-                revm::primitives::EVMError::Database(ee) => {
-                    SoflError::BcState(format!("{:?}", ee))
-                }
-            })?;
-            // inspector post-transaction hook
-            results.push(result);
-        }
+        let (_, results) =
+            execute(self, spec, None::<NoInspector>, CommitPolicy::EachTx)?;
         Ok(results)
     }
 
     /// Simulate state transition without modifying the state.
     /// Returns the state modification.
     /// Function apply_changes() can be used to apply the changes to the state.
+    /// Every transaction is executed on the original state, without seeing the previous ones.
+    /// Use `BcStateRef::simulate_ref` to simulate on a shared state,
+    /// and `BcStateRef::simulate_bundle` to simulate dependent transactions.
     fn simulate<'a, I>(
         &'a mut self,
         spec: TransitionSpec,
-        mut inspector: &mut I,
+        inspector: &mut I,
     ) -> Result<(Vec<StateChange>, Vec<ExecutionResult>), SoflError>
     where
         Self::Error: std::fmt::Debug,
         I: EvmInspector<&'a mut Self>,
     {
-        let TransitionSpec { cfg, block, txs } = spec;
-        let mut evm = revm::EVM::new();
-        evm.env.cfg = cfg;
-        evm.env.block = block;
-        evm.database(self);
-        let mut results = Vec::new();
-        let mut changes = Vec::new();
-        for tx in txs.into_iter() {
-            evm.env.tx = tx;
-
-            let insp = &mut inspector;
-            // inspector pre-transaction hook
-            if !insp.transaction(
-                &evm.env.tx,
-                evm.db
-                    .as_ref()
-                    .expect("impossible: db does not exists while database has been set in evm"),
-            ) {
-                // return false to skip transaction
-                let r = revm::primitives::ExecutionResult::Halt {
-                    reason: revm::primitives::Halt::NotActivated,
-                    gas_used: 0,
-                };
-                results.push(r);
-                changes.push(revm::primitives::State::default());
-                continue;
-            }
-
-            let revm::primitives::ResultAndState { result, state } =
-                evm.inspect(insp).map_err(|e| match e {
-                    revm::primitives::EVMError::Transaction(ee) => {
-                        SoflError::InvalidTransaction(ee)
-                    }
-                    revm::primitives::EVMError::Header(ee) => {
-                        SoflError::InvalidHeader(ee)
-                    }
-                    revm::primitives::EVMError::Database(ee) => {
-                        SoflError::BcState(format!("{:?}", ee))
-                    }
-                })?;
-
-            // inspector post-transaction hook
-            (&mut inspector).transaction_end(
-                &evm.env.tx,
-                evm.db
-                    .as_ref()
-                    .expect("impossible: db does not exists while database has been set in evm"),
-                &result,
-            );
-
-            results.push(result);
-            changes.push(state);
-        }
-        Ok((changes, results))
+        execute(self, spec, Some(inspector), CommitPolicy::Never)
     }
 
     fn apply_changes<'a>(&'a mut self, changes: Vec<StateChange>) {
@@ -308,6 +193,97 @@ pub trait BcState:
     }
 }
 
+/// When the changes of the executed transactions are committed to the state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitPolicy {
+    /// Commit after each transaction, which sees the changes of the previous ones.
+    #[default]
+    EachTx,
+    /// Never commit, the changes are returned instead.
+    Never,
+    /// Commit after each transaction like `EachTx`, and also return the changes.
+    /// Used to execute on an overlay which is discarded afterwards.
+    Overlay,
+}
+
+/// Execute the transactions of the spec on the state, committing the changes as the policy says.
+/// Returns the changes that are not committed, one per transaction, and the execution results.
+/// Without an inspector, the transaction hooks are not called either.
+/// Fails with `SoflError::Interrupted` once the inspector is interrupted,
+/// leaving the changes of the previous transactions as the policy says.
+pub fn execute<DB, I>(
+    db: DB,
+    spec: TransitionSpec,
+    mut inspector: Option<I>,
+    policy: CommitPolicy,
+) -> Result<(Vec<StateChange>, Vec<ExecutionResult>), SoflError>
+where
+    DB: BcState,
+    <DB as revm::Database>::Error: std::fmt::Debug,
+    I: EvmInspector<DB>,
+{
+    let TransitionSpec { cfg, block, txs } = spec;
+    let mut evm = revm::EVM::new();
+    evm.env.cfg = cfg;
+    evm.env.block = block;
+    evm.database(db);
+    let mut results = Vec::with_capacity(txs.len());
+    let mut changes = Vec::new();
+    for tx in txs.into_iter() {
+        evm.env.tx = tx;
+        let db = evm.db.as_ref().expect(
+            "impossible: db does not exists while database has been set in evm",
+        );
+
+        // inspector pre-transaction hook
+        if let Some(insp) = inspector.as_mut() {
+            if !insp.transaction(&evm.env.tx, db) {
+                if insp.interrupted() {
+                    return Err(SoflError::Interrupted);
+                }
+                // return false to skip transaction
+                results.push(revm::primitives::ExecutionResult::Halt {
+                    reason: revm::primitives::Halt::NotActivated,
+                    gas_used: 0,
+                });
+                if policy != CommitPolicy::EachTx {
+                    changes.push(StateChange::default());
+                }
+                continue;
+            }
+        }
+
+        // execute transaction
+        let revm::primitives::ResultAndState { result, state } =
+            match inspector.as_mut() {
+                Some(insp) => evm.inspect(insp)?,
+                None => evm.transact()?,
+            };
+        // the interrupted transaction is halted halfway and never committed
+        if inspector.as_ref().is_some_and(|insp| insp.interrupted()) {
+            return Err(SoflError::Interrupted);
+        }
+        let db = evm.db.as_mut().expect(
+            "impossible: db does not exists while database has been set in evm",
+        );
+        match policy {
+            CommitPolicy::EachTx => db.commit(state),
+            CommitPolicy::Never => changes.push(state),
+            CommitPolicy::Overlay => {
+                changes.push(state.clone());
+                db.commit(state);
+            }
+        }
+
+        // inspector post-transaction hook
+        if let Some(insp) = inspector.as_mut() {
+            insp.transaction_end(&evm.env.tx, db, &result);
+        }
+        results.push(result);
+    }
+    Ok((changes, results))
+}
+
 /// Any type that implements revm::Database auto-implements BcState.
 impl<T: revm::Database + revm::DatabaseCommit> BcState for T
 where
@@ -316,6 +292,47 @@ where
     type DatabaseErr = T::Error;
 }
 
+/// BcStateRef simulates state transitions on a read-only state,
+/// so that multiple simulations can run concurrently on a shared state.
+pub trait BcStateRef: revm::DatabaseRef + Sized {
+    /// Simulate state transition on a cache layered over the state,
+    /// which is never modified.
+    /// Returns the state modification and the execution results, the same as `BcState::simulate`.
+    fn simulate_ref<'a, I>(
+        &'a self,
+        spec: TransitionSpec,
+        inspector: &mut I,
+    ) -> Result<(Vec<StateChange>, Vec<ExecutionResult>), SoflError>
+    where
+        Self::Error: std::fmt::Debug,
+        I: for<'b> EvmInspector<&'b mut MemoryBcState<&'a Self>>,
+    {
+        let mut state = MemoryBcState::new(self);
+        execute(&mut state, spec, Some(inspector), CommitPolicy::Never)
+    }
+
+    /// Simulate a bundle of transactions on an overlay of the state,
+    /// where each transaction sees the changes of the previous ones.
+    /// The overlay is discarded and the state is never modified.
+    /// Returns the changes of each transaction, which can be applied in order
+    /// with `apply_changes()`, and the execution results.
+    fn simulate_bundle<'a, I>(
+        &'a self,
+        spec: TransitionSpec,
+        inspector: &mut I,
+    ) -> Result<(Vec<StateChange>, Vec<ExecutionResult>), SoflError>
+    where
+        Self::Error: std::fmt::Debug,
+        I: for<'b> EvmInspector<&'b mut MemoryBcState<&'a Self>>,
+    {
+        let mut overlay = MemoryBcState::new(self);
+        execute(&mut overlay, spec, Some(inspector), CommitPolicy::Overlay)
+    }
+}
+
+/// Any type that implements revm::DatabaseRef auto-implements BcStateRef.
+impl<T: revm::DatabaseRef> BcStateRef for T {}
+
 // /// BcState wraps revm's DatabaseCommit trait.
 // /// It provides a set of basic methods to edit the state of the blockchain.
 // pub trait BcStateEditable: BcState + revm::DatabaseCommit
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SoflError;
+
+use super::{
+    state::BcState,
+    types::{Address, Bytes, StateChange, B256, KECCAK_EMPTY, U256},
+};
+
+/// State of an account in a `StateDiff`, where unchanged fields are omitted.
+/// Storage slots are 32-byte words, and zero values are omitted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<B256, B256>,
+}
+
+impl AccountState {
+    fn is_empty(&self) -> bool {
+        self.balance.is_none()
+            && self.nonce.is_none()
+            && self.code.is_none()
+            && self.storage.is_empty()
+    }
+}
+
+/// State changed by a transaction, in the same form as the diff mode of geth's `prestateTracer`:
+/// - `pre`: the state of modified accounts before the transaction, with the balance, the nonce
+///   and the code (if not zero or empty) and the modified storage slots.
+///   Accounts created by the transaction are not included.
+/// - `post`: the modified fields and storage slots after the transaction.
+///   Accounts destroyed by the transaction are not included.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub pre: BTreeMap<Address, AccountState>,
+    pub post: BTreeMap<Address, AccountState>,
+}
+
+impl StateDiff {
+    /// Compute the diff of the state changes from `BcState::simulate`,
+    /// with `state` being the state before the changes are applied.
+    pub fn new<BS: BcState>(
+        state: &mut BS,
+        changes: &StateChange,
+    ) -> Result<Self, SoflError>
+    where
+        BS::Error: std::fmt::Debug,
+    {
+        let state_err = |e| SoflError::BcState(format!("{:?}", e));
+        let mut diff = Self::default();
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            let info = state.basic(*address).map_err(state_err)?;
+            let existed = info.is_some();
+            let info = info.unwrap_or_default();
+            let code = if info.code_hash == KECCAK_EMPTY {
+                Bytes::new()
+            } else {
+                match &info.code {
+                    Some(code) => code.original_bytes(),
+                    None => state
+                        .code_by_hash(info.code_hash)
+                        .map_err(state_err)?
+                        .original_bytes(),
+                }
+            };
+            let mut pre = AccountState {
+                balance: Some(info.balance),
+                nonce: (info.nonce != 0).then_some(info.nonce),
+                code: (!code.is_empty()).then_some(code),
+                storage: BTreeMap::new(),
+            };
+
+            // the state of a destroyed account is only kept in `pre`
+            if account.is_selfdestructed() {
+                if existed {
+                    for (slot, value) in &account.storage {
+                        let original = value.original_value();
+                        if original != U256::ZERO {
+                            pre.storage.insert((*slot).into(), original.into());
+                        }
+                    }
+                    diff.pre.insert(*address, pre);
+                }
+                continue;
+            }
+
+            let mut post = AccountState::default();
+            if account.info.balance != info.balance {
+                post.balance = Some(account.info.balance);
+            }
+            if account.info.nonce != info.nonce {
+                post.nonce = Some(account.info.nonce);
+            }
+            if account.info.code_hash != info.code_hash {
+                post.code = Some(
+                    account
+                        .info
+                        .code
+                        .as_ref()
+                        .map(|c| c.original_bytes())
+                        .unwrap_or_default(),
+                );
+            }
+            let mut modified = !post.is_empty();
+            for (slot, value) in &account.storage {
+                let (original, present) =
+                    (value.original_value(), value.present_value());
+                if original == present {
+                    continue;
+                }
+                modified = true;
+                if original != U256::ZERO {
+                    pre.storage.insert((*slot).into(), original.into());
+                }
+                if present != U256::ZERO {
+                    post.storage.insert((*slot).into(), present.into());
+                }
+            }
+            if !modified {
+                continue;
+            }
+            if existed || !account.is_created() {
+                diff.pre.insert(*address, pre);
+            }
+            diff.post.insert(*address, post);
+        }
+        Ok(diff)
+    }
+
+    /// Accounts created by the transaction.
+    pub fn created(&self) -> impl Iterator<Item = &Address> + '_ {
+        self.post.keys().filter(|a| !self.pre.contains_key(a))
+    }
+
+    /// Accounts destroyed by the transaction.
+    pub fn destroyed(&self) -> impl Iterator<Item = &Address> + '_ {
+        self.pre.keys().filter(|a| !self.post.contains_key(a))
+    }
+
+    /// Storage slots changed by the transaction, with their values before and after.
+    pub fn storage_changes(
+        &self,
+    ) -> impl Iterator<Item = (Address, B256, B256, B256)> + '_ {
+        let slots = |address: &Address| {
+            let pre = self.pre.get(address).map(|a| &a.storage);
+            let post = self.post.get(address).map(|a| &a.storage);
+            let mut slots: Vec<_> = pre
+                .into_iter()
+                .chain(post)
+                .flat_map(|s| s.keys().copied())
+                .collect();
+            slots.sort();
+            slots.dedup();
+            slots.into_iter().map(move |slot| {
+                let value = |s: Option<&BTreeMap<B256, B256>>| {
+                    s.and_then(|s| s.get(&slot)).copied().unwrap_or_default()
+                };
+                (*address, slot, value(pre), value(post))
+            })
+        };
+        let mut addresses: Vec<_> =
+            self.pre.keys().chain(self.post.keys()).copied().collect();
+        addresses.sort();
+        addresses.dedup();
+        addresses
+            .into_iter()
+            .flat_map(move |address| slots(&address).collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        conversion::ConvertTo,
+        engine::{
+            inspector::no_inspector,
+            memory::MemoryBcState,
+            state::BcState,
+            transition::TransitionSpecBuilder,
+            types::{
+                AccountInfo, Address, Bytecode, TransactTo, TxEnv, B256, U256,
+            },
+        },
+    };
+
+    use super::StateDiff;
+
+    #[test]
+    fn test_state_diff_of_balance_nonce_and_storage() {
+        let mut state = MemoryBcState::fresh();
+        let sender: Address = 0x01.cvt();
+        let contract: Address = 0x10.cvt();
+        // sstore(0, 1); sstore(1, 0)
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x01, 0x55, 0x00,
+        ];
+        let bytecode = Bytecode::new_raw(code.clone().into());
+        state.insert_account_info(
+            contract,
+            AccountInfo {
+                balance: U256::from(5),
+                code_hash: bytecode.hash_slow(),
+                code: Some(bytecode),
+                ..Default::default()
+            },
+        );
+        state
+            .insert_account_storage(contract, U256::from(1), U256::from(9))
+            .unwrap();
+
+        let mut tx = TxEnv::default();
+        tx.caller = sender;
+        tx.transact_to = TransactTo::Call(contract);
+        tx.value = U256::from(2);
+        tx.gas_limit = 1_000_000;
+        state.add_ether_balance(sender, U256::from(10)).unwrap();
+        let spec = TransitionSpecBuilder::default()
+            .bypass_check()
+            .append_tx_env(tx)
+            .build();
+        let (mut changes, _) = state.simulate(spec, no_inspector()).unwrap();
+        let diff = StateDiff::new(&mut state, &changes.pop().unwrap()).unwrap();
+
+        let slot = |i: u64| B256::from(U256::from(i));
+        let sender_pre = &diff.pre[&sender];
+        assert_eq!(sender_pre.balance, Some(U256::from(10)));
+        assert_eq!(sender_pre.nonce, None);
+        let sender_post = &diff.post[&sender];
+        assert_eq!(sender_post.balance, Some(U256::from(8)));
+        assert_eq!(sender_post.nonce, Some(1));
+        let contract_pre = &diff.pre[&contract];
+        assert_eq!(contract_pre.balance, Some(U256::from(5)));
+        assert_eq!(contract_pre.code, Some(code.clone().into()));
+        assert_eq!(contract_pre.storage, [(slot(1), slot(9))].into());
+        let contract_post = &diff.post[&contract];
+        assert_eq!(contract_post.balance, Some(U256::from(7)));
+        assert_eq!(contract_post.code, None);
+        assert_eq!(contract_post.storage, [(slot(0), slot(1))].into());
+        // the coinbase is touched but not modified
+        assert_eq!(diff.pre.len(), 2);
+        assert_eq!(diff.post.len(), 2);
+
+        // same json as geth
+        let json = serde_json::to_value(&diff).unwrap();
+        let key = |a: Address| serde_json::to_value(a).unwrap();
+        let post = &json["post"][key(sender).as_str().unwrap()];
+        assert_eq!(post, &json!({ "balance": "0x8", "nonce": 1 }));
+        let storage = &json["post"][key(contract).as_str().unwrap()]["storage"];
+        assert_eq!(
+            storage,
+            &json!({
+                "0x0000000000000000000000000000000000000000000000000000000000000000":
+                    "0x0000000000000000000000000000000000000000000000000000000000000001"
+            })
+        );
+        assert_eq!(serde_json::from_value::<StateDiff>(json).unwrap(), diff);
+
+        assert_eq!(diff.created().count(), 0);
+        assert_eq!(diff.destroyed().count(), 0);
+        let changes: Vec<_> = diff.storage_changes().collect();
+        assert_eq!(
+            changes,
+            vec![
+                (contract, slot(0), B256::ZERO, slot(1)),
+                (contract, slot(1), slot(9), B256::ZERO),
+            ]
+        );
+    }
+}
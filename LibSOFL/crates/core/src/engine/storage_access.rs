@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    inspector::EvmInspector,
+    state::BcState,
+    types::{
+        opcode, Address, Bytes, CallInputs, CreateInputs, Database, EVMData,
+        Gas, Inspector, InstructionResult, Interpreter, U256,
+    },
+};
+
+/// A storage slot read (`SLOAD`) or written (`SSTORE`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotAccess {
+    /// Index of the call frame doing the access, starting from 1 for the outermost one.
+    /// Call frames are numbered in the order they start, including creations and selfdestructs.
+    pub call_index: usize,
+    /// The contract whose storage is accessed.
+    pub address: Address,
+    pub slot: U256,
+    /// Value of the slot before the access.
+    pub old: U256,
+    /// Value of the slot after the access, same as `old` for reads.
+    pub new: U256,
+    pub write: bool,
+    /// Whether the access happens inside a reverted call frame.
+    pub reverted: bool,
+}
+
+/// Inspector recording storage accesses in the order they happen.
+#[derive(Clone, Debug, Default)]
+pub struct StorageAccessInspector {
+    pub accesses: Vec<SlotAccess>,
+    /// Number of call frames started so far.
+    calls: usize,
+    /// Stack of call frames, with their indices and the number of accesses when they start.
+    frames: Vec<(usize, usize)>,
+    /// Access of the current opcode and whether its old value is known,
+    /// which is recorded if the opcode succeeds.
+    pending: Option<(SlotAccess, bool)>,
+}
+
+impl StorageAccessInspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear the recorded accesses.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Distinct slots read by the contract in order of first access,
+    /// including those read by `SSTORE`.
+    pub fn reads(&self, address: Address) -> Vec<U256> {
+        Self::distinct(self.accesses.iter().filter(|a| a.address == address))
+    }
+
+    /// Distinct slots written by the contract in order of first write.
+    pub fn writes(&self, address: Address) -> Vec<U256> {
+        Self::distinct(
+            self.accesses
+                .iter()
+                .filter(|a| a.address == address && a.write),
+        )
+    }
+
+    fn distinct<'a>(
+        accesses: impl Iterator<Item = &'a SlotAccess>,
+    ) -> Vec<U256> {
+        let mut seen = BTreeSet::new();
+        accesses
+            .map(|a| a.slot)
+            .filter(|slot| seen.insert(*slot))
+            .collect()
+    }
+
+    pub fn has_writes(&self) -> bool {
+        self.accesses.iter().any(|a| a.write)
+    }
+
+    /// Accesses grouped by the contract whose storage is accessed.
+    pub fn by_contract(&self) -> BTreeMap<Address, Vec<&SlotAccess>> {
+        let mut grouped: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for access in &self.accesses {
+            grouped.entry(access.address).or_default().push(access);
+        }
+        grouped
+    }
+
+    /// Accesses grouped by the call frame doing the access.
+    pub fn by_call(&self) -> BTreeMap<usize, Vec<&SlotAccess>> {
+        let mut grouped: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for access in &self.accesses {
+            grouped.entry(access.call_index).or_default().push(access);
+        }
+        grouped
+    }
+
+    fn push_frame(&mut self) {
+        self.calls += 1;
+        self.frames.push((self.calls, self.accesses.len()));
+    }
+
+    fn pop_frame(&mut self, result: InstructionResult) {
+        let (_, start) = self.frames.pop().unwrap_or_default();
+        if !result.is_ok() {
+            self.accesses[start..]
+                .iter_mut()
+                .for_each(|a| a.reverted = true);
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for StorageAccessInspector {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        data: &mut EVMData<'_, DB>,
+    ) {
+        let op = interp.current_opcode();
+        if op != opcode::SLOAD && op != opcode::SSTORE {
+            return;
+        }
+        let Ok(slot) = interp.stack().peek(0) else {
+            return;
+        };
+        let address = interp.contract.address;
+        // the old value is the present value if the slot is loaded,
+        // otherwise the original value after the opcode is executed
+        let old = data
+            .journaled_state
+            .state
+            .get(&address)
+            .and_then(|a| a.storage.get(&slot))
+            .map(|s| s.present_value());
+        let access = SlotAccess {
+            call_index: self.frames.last().map(|f| f.0).unwrap_or_default(),
+            address,
+            slot,
+            old: old.unwrap_or_default(),
+            new: U256::ZERO,
+            write: op == opcode::SSTORE,
+            reverted: false,
+        };
+        self.pending = Some((access, old.is_some()));
+    }
+
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        data: &mut EVMData<'_, DB>,
+    ) {
+        let Some((mut access, old_known)) = self.pending.take() else {
+            return;
+        };
+        if interp.instruction_result != InstructionResult::Continue {
+            return;
+        }
+        let Some(slot) = data
+            .journaled_state
+            .state
+            .get(&access.address)
+            .and_then(|a| a.storage.get(&access.slot))
+        else {
+            return;
+        };
+        if !old_known {
+            access.old = slot.original_value();
+        }
+        access.new = slot.present_value();
+        self.accesses.push(access);
+    }
+
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.push_frame();
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.pop_frame(ret);
+        (ret, gas, out)
+    }
+
+    fn create(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.push_frame();
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(inputs.gas_limit),
+            Bytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.pop_frame(ret);
+        (ret, address, remaining_gas, out)
+    }
+
+    fn selfdestruct(
+        &mut self,
+        _contract: Address,
+        _target: Address,
+        _value: U256,
+    ) {
+        // selfdestruct is a call frame without opcodes
+        self.calls += 1;
+    }
+}
+
+impl<BS: BcState> EvmInspector<BS> for StorageAccessInspector {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        conversion::ConvertTo,
+        engine::{
+            memory::MemoryBcState,
+            state::BcState,
+            transition::TransitionSpecBuilder,
+            types::{AccountInfo, Address, Bytecode, TransactTo, TxEnv, U256},
+        },
+    };
+
+    use super::StorageAccessInspector;
+
+    fn deploy<S: BcState>(state: &mut S, address: Address, code: &[u8]) {
+        let bytecode = Bytecode::new_raw(code.to_vec().into());
+        state.insert_account_info(
+            address,
+            AccountInfo {
+                code_hash: bytecode.hash_slow(),
+                code: Some(bytecode),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn test_record_storage_accesses() {
+        let mut state = MemoryBcState::fresh();
+        let a: Address = 0x10.cvt();
+        let b: Address = 0x20.cvt();
+        // sstore(0, 1); pop(sload(0)); pop(call(gas(), 0x20, 0, 0, 0, 0, 0))
+        deploy(
+            &mut state,
+            a,
+            &[
+                0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x54, 0x50, 0x60,
+                0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60,
+                0x20, 0x5a, 0xf1, 0x50, 0x00,
+            ],
+        );
+        // sstore(0, 7); revert(0, 0)
+        deploy(
+            &mut state,
+            b,
+            &[0x60, 0x07, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd],
+        );
+
+        let mut tx = TxEnv::default();
+        tx.transact_to = TransactTo::Call(a);
+        tx.gas_limit = 1_000_000;
+        let spec = TransitionSpecBuilder::default()
+            .bypass_check()
+            .append_tx_env(tx)
+            .build();
+        let mut inspector = StorageAccessInspector::new();
+        state.transit(spec, &mut inspector).unwrap();
+
+        let accesses = &inspector.accesses;
+        assert_eq!(accesses.len(), 3);
+        assert!(accesses[0].write && accesses[0].call_index == 1);
+        assert_eq!(
+            (accesses[0].old, accesses[0].new),
+            (U256::ZERO, U256::from(1))
+        );
+        assert!(!accesses[1].write && !accesses[1].reverted);
+        assert_eq!(
+            (accesses[1].old, accesses[1].new),
+            (U256::from(1), U256::from(1))
+        );
+        assert_eq!(accesses[2].call_index, 2);
+        assert_eq!(accesses[2].address, b);
+        assert_eq!(accesses[2].new, U256::from(7));
+        assert!(accesses[2].reverted);
+
+        // the slot is read twice, but returned once
+        assert_eq!(inspector.reads(a), vec![U256::ZERO]);
+        assert_eq!(inspector.writes(a), vec![U256::ZERO]);
+        assert_eq!(inspector.by_call().len(), 2);
+        assert_eq!(inspector.by_contract()[&b].len(), 1);
+    }
+}
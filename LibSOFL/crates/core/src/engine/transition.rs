@@ -2,12 +2,13 @@ use revm_primitives::{BlockEnv, CfgEnv, TxEnv};
 
 use crate::{
     blockchain::{
-        provider::BcProvider, transaction::Tx, tx_position::TxPosition,
+        chain::ChainConfig, provider::BcProvider, transaction::Tx,
+        tx_position::TxPosition,
     },
     error::SoflError,
 };
 
-use super::types::{BlockHashOrNumber, TxHash};
+use super::types::{BlockHashOrNumber, SpecId, TxHash};
 
 #[derive(Default, Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct TransitionSpec {
@@ -57,6 +58,10 @@ pub struct TransitionSpecBuilder {
     block: BlockEnv,
     txs: Vec<TxEnv>,
     bypass_check: bool,
+    /// Select the spec by the block from the chain when built.
+    chain: Option<ChainConfig>,
+    /// Override the spec of the cfg and the chain when built.
+    spec_id: Option<SpecId>,
 }
 
 impl TransitionSpecBuilder {
@@ -67,6 +72,16 @@ impl TransitionSpecBuilder {
 
 impl TransitionSpecBuilder {
     pub fn build(mut self) -> TransitionSpec {
+        if let Some(chain) = &self.chain {
+            self.cfg.chain_id = chain.chain_id;
+            self.cfg.spec_id = chain.spec_at(
+                self.block.number.saturating_to(),
+                self.block.timestamp.saturating_to(),
+            );
+        }
+        if let Some(spec_id) = self.spec_id {
+            self.cfg.spec_id = spec_id;
+        }
         if self.bypass_check {
             self.cfg.disable_balance_check = true;
             self.cfg.disable_base_fee = true;
@@ -106,6 +121,19 @@ impl TransitionSpecBuilder {
         self
     }
 
+    /// Use the hardfork of the chain at the block, instead of the spec in the cfg.
+    pub fn set_chain(mut self, chain: ChainConfig) -> Self {
+        self.chain = Some(chain);
+        self
+    }
+
+    /// Execute with the given hardfork regardless of the block, e.g., to replay
+    /// historical transactions under counterfactual forks.
+    pub fn set_spec_id(mut self, spec_id: SpecId) -> Self {
+        self.spec_id = Some(spec_id);
+        self
+    }
+
     pub fn append_tx<T: Tx>(self, tx: T) -> Self {
         let mut tx_env = TxEnv::default();
         tx.fill_tx_env(&mut tx_env)
@@ -35,3 +35,19 @@ pub enum SoflError {
     #[display(fmt = "Err: {}", _0)]
     Custom(String),
 }
+
+impl<E: std::fmt::Debug> From<revm::primitives::EVMError<E>> for SoflError {
+    fn from(e: revm::primitives::EVMError<E>) -> Self {
+        match e {
+            revm::primitives::EVMError::Transaction(ee) => {
+                SoflError::InvalidTransaction(ee)
+            }
+            revm::primitives::EVMError::Header(ee) => {
+                SoflError::InvalidHeader(ee)
+            }
+            revm::primitives::EVMError::Database(ee) => {
+                SoflError::BcState(format!("{:?}", ee))
+            }
+        }
+    }
+}
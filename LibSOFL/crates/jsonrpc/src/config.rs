@@ -1,4 +1,7 @@
-use libsofl_core::error::SoflError;
+use libsofl_core::{
+    blockchain::chain::{parse_spec_id, ChainConfig},
+    error::SoflError,
+};
 use libsofl_utils::config::Config;
 
 use crate::provider::JsonRpcProvider;
@@ -8,6 +11,13 @@ use crate::provider::JsonRpcProvider;
 )]
 pub struct JsonRpcConfig {
     pub url: String,
+    /// `mainnet`, `sepolia`, `holesky` or the path of a genesis JSON file,
+    /// inferred from the chain id if not set.
+    #[serde(default)]
+    pub chain: Option<String>,
+    /// Hardfork to execute all blocks with, e.g., `london`.
+    #[serde(default)]
+    pub spec_id: Option<String>,
 }
 
 impl Config for JsonRpcConfig {
@@ -18,6 +28,13 @@ impl Config for JsonRpcConfig {
 
 impl JsonRpcConfig {
     pub fn bc_provider(&self) -> Result<JsonRpcProvider, SoflError> {
-        JsonRpcProvider::new(self.url.clone())
+        let mut provider = JsonRpcProvider::new(self.url.clone())?;
+        if let Some(chain) = &self.chain {
+            provider = provider.with_chain(ChainConfig::load(chain)?);
+        }
+        if let Some(spec_id) = &self.spec_id {
+            provider = provider.with_spec_id(parse_spec_id(spec_id)?);
+        }
+        Ok(provider)
     }
 }
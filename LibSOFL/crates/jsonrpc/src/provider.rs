@@ -5,7 +5,8 @@ use alloy_rpc_types::{Block, BlockNumberOrTag};
 use alloy_transport_http::Http;
 use libsofl_core::{
     blockchain::{
-        provider::BcProvider, transaction::Tx, tx_position::TxPosition,
+        chain::ChainConfig, provider::BcProvider, transaction::Tx,
+        tx_position::TxPosition,
     },
     conversion::ConvertTo,
     engine::types::{
@@ -26,6 +27,11 @@ pub struct JsonRpcProvider {
 
     pub(crate) rt: AsyncRuntime,
 
+    /// Hardfork schedule to select the spec of each block.
+    pub chain: ChainConfig,
+    /// Spec used for all blocks instead of the one of the chain.
+    pub spec_id: Option<SpecId>,
+
     // caches
     pub(crate) chain_id: u64,
     pub(crate) txs: RefCell<HashMap<TxHashOrPosition, JsonRpcTx>>,
@@ -45,11 +51,20 @@ impl JsonRpcProvider {
         let chain_id = rt.block_on(p.get_chain_id()).map_err(|e| {
             SoflError::Provider(format!("failed to get chain id: {:?}", e))
         })?;
+        let chain_id: u64 = chain_id.cvt();
+        // unknown chains follow the hardforks of mainnet
+        let chain =
+            ChainConfig::by_chain_id(chain_id).unwrap_or_else(|| ChainConfig {
+                chain_id,
+                ..ChainConfig::mainnet()
+            });
         Ok(JsonRpcProvider {
             url,
             p,
             rt,
-            chain_id: chain_id.cvt(),
+            chain,
+            spec_id: None,
+            chain_id,
             txs: Default::default(),
             txs_in_block: Default::default(),
             block_by_hash: Default::default(),
@@ -64,6 +79,8 @@ impl Clone for JsonRpcProvider {
             url: self.url.clone(),
             p: self.p.clone(),
             rt: self.rt.clone(),
+            chain: self.chain.clone(),
+            spec_id: self.spec_id,
             chain_id: self.chain_id,
             txs: self.txs.clone(),
             txs_in_block: self.txs_in_block.clone(),
@@ -73,6 +90,20 @@ impl Clone for JsonRpcProvider {
     }
 }
 
+impl JsonRpcProvider {
+    /// Select the spec of each block with the hardforks of the given chain.
+    pub fn with_chain(mut self, chain: ChainConfig) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Execute all blocks with the given spec.
+    pub fn with_spec_id(mut self, spec_id: SpecId) -> Self {
+        self.spec_id = Some(spec_id);
+        self
+    }
+}
+
 impl JsonRpcProvider {
     fn block(&self, block: BlockHashOrNumber) -> Result<Block, SoflError> {
         match block {
@@ -230,30 +261,19 @@ impl BcProvider<JsonRpcTx> for JsonRpcProvider {
         env: &mut CfgEnv,
         block: BlockHashOrNumber,
     ) -> Result<(), SoflError> {
-        let number = match block {
-            BlockHashOrNumber::Hash(hash) => self.block_number_by_hash(hash)?,
-            BlockHashOrNumber::Number(number) => number,
-        };
+        let header = self.block(block)?.header;
+        let number = header.number.ok_or(SoflError::NotFound(format!(
+            "block number not available {}",
+            block
+        )))?;
         env.chain_id = self.chain_id;
         env.perf_analyse_created_bytecodes = AnalysisKind::Analyse;
-        env.spec_id = match number {
-            0..=199999 => SpecId::FRONTIER,
-            200000..=1149999 => SpecId::FRONTIER_THAWING,
-            1150000..=1919999 => SpecId::HOMESTEAD,
-            1920000..=2462999 => SpecId::DAO_FORK,
-            2463000..=2674999 => SpecId::TANGERINE,
-            2675000..=4369999 => SpecId::SPURIOUS_DRAGON,
-            4370000..=7279999 => SpecId::BYZANTIUM,
-            // 7280000..9069000 => SpecId::CONSTANTINOPLE,
-            7280000..=9068999 => SpecId::PETERSBURG,
-            9069000..=9199999 => SpecId::ISTANBUL,
-            9200000..=12243999 => SpecId::MUIR_GLACIER,
-            12244000..=12964999 => SpecId::BERLIN,
-            12965000..=13772999 => SpecId::LONDON,
-            13773000..=15049999 => SpecId::ARROW_GLACIER,
-            15050000..=15537393 => SpecId::GRAY_GLACIER,
-            15537394..=17034869 => SpecId::MERGE,
-            17034870.. => SpecId::SHANGHAI,
+        env.spec_id = match self.spec_id {
+            Some(spec_id) => spec_id,
+            None => self.chain.spec_at(
+                number.saturating_to(),
+                header.timestamp.saturating_to(),
+            ),
         };
         Ok(())
     }
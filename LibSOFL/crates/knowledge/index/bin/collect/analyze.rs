@@ -1,4 +1,8 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::HashSet,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
 use libsofl_core::{
     blockchain::{
@@ -7,6 +11,7 @@ use libsofl_core::{
     },
     conversion::ConvertTo,
     engine::{
+        guard::ExecutionGuard,
         inspector::CombinedInspector,
         state::BcState,
         transition::TransitionSpec,
@@ -18,7 +23,7 @@ use libsofl_knowledge_index::inspectors::{
     extract_creation::ExtractCreationInspector,
     extract_invocation::ExtractInvocationInspector,
 };
-use libsofl_utils::log::debug;
+use libsofl_utils::log::{debug, warn};
 
 pub struct Analyzer<
     T: Tx,
@@ -28,6 +33,12 @@ pub struct Analyzer<
     S::Error: std::fmt::Debug,
 {
     provider: Arc<P>,
+    /// Set to stop the blocks being analyzed.
+    cancelled: Arc<AtomicBool>,
+    /// Maximum number of opcodes executed by each transaction.
+    max_steps: Option<u64>,
+    /// Maximum time to analyze each block.
+    timeout: Option<Duration>,
 
     _phantom: std::marker::PhantomData<(T, S)>,
 }
@@ -40,6 +51,9 @@ where
     fn clone(&self) -> Self {
         Self {
             provider: self.provider.clone(),
+            cancelled: self.cancelled.clone(),
+            max_steps: self.max_steps,
+            timeout: self.timeout,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -53,9 +67,40 @@ where
     pub fn new(provider: Arc<P>) -> Self {
         Self {
             provider,
+            cancelled: Default::default(),
+            max_steps: None,
+            timeout: None,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Stop the analysis of blocks once the flag is set.
+    pub fn with_cancellation(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn guard(&self) -> ExecutionGuard {
+        let mut guard =
+            ExecutionGuard::new().with_cancellation(self.cancelled.clone());
+        if let Some(max_steps) = self.max_steps {
+            guard = guard.with_max_steps(max_steps);
+        }
+        if let Some(timeout) = self.timeout {
+            guard = guard.with_timeout(timeout);
+        }
+        guard
+    }
 }
 
 impl<T: Tx, S: DatabaseRef, P: BcProvider<T> + BcStateProvider<S>>
@@ -76,6 +121,7 @@ where
 
         let mut total_creations = Vec::new();
         let mut total_invocations = HashSet::new();
+        let mut guard = self.guard();
 
         for tx in txs {
             let mut tx_env = TxEnv::default();
@@ -91,12 +137,22 @@ where
             let mut insp = CombinedInspector::default();
             insp.add(&mut creation_insp);
             insp.add(&mut invocation_insp);
+            insp.add(&mut guard);
 
-            state.transit(spec, &mut insp)?;
+            let result = state.transit(spec, &mut insp);
 
             drop(insp);
 
             let tx_hash: String = tx.hash().cvt();
+            if let Some(reason) = guard.interruption {
+                warn!(
+                    block = block,
+                    tx = tx_hash,
+                    reason = reason.to_string(),
+                    "transaction execution interrupted"
+                );
+            }
+            result?;
             let creations: Vec<(String, String, bool)> = creation_insp
                 .created
                 .iter()
@@ -1,4 +1,10 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use clap::Parser;
 use data::DataStore;
@@ -37,6 +43,15 @@ struct Arg {
 
     #[arg(short, long, default_value = "100")]
     db_flush_threshold: u64,
+
+    #[arg(
+        long,
+        help = "maximum number of opcodes executed by each transaction"
+    )]
+    max_steps: Option<u64>,
+
+    #[arg(long, help = "maximum seconds to analyze each block")]
+    timeout: Option<u64>,
 }
 
 #[tokio::main(worker_threads = 32)]
@@ -46,7 +61,7 @@ async fn main() {
     // prepare logger
     let indicatif_layer = IndicatifLayer::new();
     let log_filter = EnvFilter::try_from_default_env()
-        .or_else(|_| EnvFilter::try_new(args.level))
+        .or_else(|_| EnvFilter::try_new(&args.level))
         .expect("failed to create console logger filter");
     tracing_subscriber::registry()
         .with(
@@ -64,18 +79,22 @@ async fn main() {
     );
 
     let cancellation_token = CancellationToken::new();
+    // stops the executions of in-flight blocks
+    let cancelled = Arc::new(AtomicBool::new(false));
 
     // handle signals
     let mut signals =
         Signals::new(&[SIGINT, SIGTERM]).expect("failed to register signals");
     let handle = signals.handle();
     let cancel = cancellation_token.clone();
+    let cancel_execution = cancelled.clone();
     let signal_task = tokio::spawn(async move {
         while let Some(sig) = signals.next().await {
             match sig {
                 SIGINT | SIGTERM => {
                     info!(sig = sig, "received signal, stopping...");
                     cancel.cancel();
+                    cancel_execution.store(true, Ordering::Relaxed);
                 }
                 _ => unreachable!(),
             }
@@ -83,14 +102,8 @@ async fn main() {
     });
 
     let task_tracker = TaskTracker::new();
-    collect_blocks(
-        args.until_block,
-        args.jobs,
-        cancellation_token.clone(),
-        &task_tracker,
-        args.db_flush_threshold,
-    )
-    .await;
+    collect_blocks(&args, cancellation_token.clone(), cancelled, &task_tracker)
+        .await;
     task_tracker.close();
     task_tracker.wait().await;
 
@@ -99,12 +112,13 @@ async fn main() {
 }
 
 async fn collect_blocks(
-    until_block: u64,
-    step: usize,
+    args: &Arg,
     cancellation_token: CancellationToken,
+    cancelled: Arc<AtomicBool>,
     task_tracker: &TaskTracker,
-    db_flush_threshold: u64,
 ) {
+    let until_block = args.until_block;
+    let step = args.jobs;
     let cfg = KnowledgeConfig::load_or(Default::default())
         .expect("failed to load config");
     let db = cfg.get_database_connection().await.unwrap();
@@ -113,8 +127,15 @@ async fn collect_blocks(
     let provider = cfg.bc_provider().unwrap();
     info!(datadir = cfg.datadir, "reth blockchain provider connected");
     let provider = Arc::new(provider);
-    let analyzer = analyze::Analyzer::new(provider);
-    let mut store = DataStore::new(&db, db_flush_threshold).await.unwrap();
+    let mut analyzer =
+        analyze::Analyzer::new(provider).with_cancellation(cancelled);
+    if let Some(max_steps) = args.max_steps {
+        analyzer = analyzer.with_max_steps(max_steps);
+    }
+    if let Some(timeout) = args.timeout {
+        analyzer = analyzer.with_timeout(Duration::from_secs(timeout));
+    }
+    let mut store = DataStore::new(&db, args.db_flush_threshold).await.unwrap();
 
     let range = (store.get_last_finished_block() + 1)..until_block;
 
@@ -178,7 +199,10 @@ async fn collect_blocks(
                         }
                     }
                 }
-                Err(SoflError::Interrupted) => {
+                // a block hitting the step budget or timeout fails like other errors
+                Err(SoflError::Interrupted)
+                    if cancellation_token.is_cancelled() =>
+                {
                     warn!(block = bn, "block analysis interrupted");
                     break;
                 }
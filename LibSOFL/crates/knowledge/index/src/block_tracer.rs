@@ -0,0 +1,326 @@
+//! Tracing all transactions of a block with a single replay of the block.
+//!
+//! Tracing transactions of a block one by one builds the state before each of them,
+//! which replays all preceding transactions of the block every time.
+//! `BlockTracer` builds the state before the block once and traces each transaction
+//! on top of the previous ones.
+
+use std::{fmt::Debug, sync::Arc};
+
+use libsofl_core::{
+    blockchain::{
+        provider::{BcProvider, BcStateProvider},
+        transaction::Tx,
+        tx_position::TxPosition,
+    },
+    conversion::ConvertTo,
+    engine::{
+        state::{BcState, DatabaseRef},
+        state_diff::StateDiff,
+        transition::TransitionSpec,
+        types::BlockNumber,
+    },
+    error::SoflError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::EventRegistry,
+    inspectors::tx_tracer::{TraceConfig, TxTracer},
+    selectors::SelectorDb,
+    trace::TxTrace,
+    valuation::Valuator,
+};
+
+/// Summary of the transactions in a traced block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub block: BlockNumber,
+    pub txs: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub gas_used: u64,
+    pub money_flows: usize,
+    /// Call frames, including those of reverted transactions.
+    pub calls: usize,
+    /// Logs in the receipts.
+    pub logs: usize,
+}
+
+impl BlockSummary {
+    pub fn new(block: BlockNumber, traces: &[TxTrace]) -> Self {
+        let mut summary = Self {
+            block,
+            txs: traces.len(),
+            ..Default::default()
+        };
+        for trace in traces {
+            summary.money_flows += trace.money_flows.len();
+            summary.calls += trace.calls.len();
+            let Some(meta) = &trace.meta else {
+                continue;
+            };
+            if meta.success {
+                summary.succeeded += 1;
+            } else {
+                summary.failed += 1;
+            }
+            summary.gas_used += meta.gas_used;
+            summary.logs += meta.logs;
+        }
+        summary
+    }
+}
+
+/// Traces of the transactions in a block, in the order they are executed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockTrace {
+    pub summary: BlockSummary,
+    pub traces: Vec<TxTrace>,
+}
+
+/// Tracer of whole blocks, with the same options as `TxTracer`.
+#[derive(Clone, Debug, Default)]
+pub struct BlockTracer {
+    pub config: TraceConfig,
+    /// Decoders of token events, the standard ones by default.
+    pub registry: EventRegistry,
+    /// Decoders of function calls, no call is decoded if `None`.
+    pub selectors: Option<Arc<SelectorDb>>,
+    /// Whether to record the state diff of each transaction.
+    pub state_diff: bool,
+    /// Values the money flows of each transaction at the state before it, if set.
+    pub valuator: Option<Valuator>,
+}
+
+impl BlockTracer {
+    pub fn new(config: TraceConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Decode token events with the given registry.
+    pub fn with_registry(mut self, registry: EventRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Decode message calls with the given selector database.
+    pub fn with_selectors(mut self, selectors: Arc<SelectorDb>) -> Self {
+        self.selectors = Some(selectors);
+        self
+    }
+
+    /// Record the state diff of each transaction.
+    pub fn with_state_diff(mut self, state_diff: bool) -> Self {
+        self.state_diff = state_diff;
+        self
+    }
+
+    /// Value the money flows of each transaction with the valuator.
+    pub fn with_valuator(mut self, valuator: Valuator) -> Self {
+        self.valuator = Some(valuator);
+        self
+    }
+
+    /// Trace all transactions of the block, with their hashes and the block number
+    /// set in the metadata of the traces.
+    pub fn trace_block<T, S, P>(
+        &self,
+        provider: &P,
+        block: BlockNumber,
+    ) -> Result<BlockTrace, SoflError>
+    where
+        T: Tx,
+        S: DatabaseRef,
+        S::Error: Debug,
+        P: BcProvider<T> + BcStateProvider<S>,
+    {
+        let txs = provider.txs_in_block(block.cvt())?;
+        let mut spec = TransitionSpec::default();
+        provider.fill_cfg_env(&mut spec.cfg, block.cvt())?;
+        provider.fill_block_env(&mut spec.block, block.cvt())?;
+        for tx in &txs {
+            let mut tx_env = Default::default();
+            tx.fill_tx_env(&mut tx_env)?;
+            spec.txs.push(tx_env);
+        }
+
+        let mut state = provider.bc_state_at(TxPosition::new(block, 0))?;
+        let mut trace = self.trace(&mut state, spec)?;
+        for (trace, tx) in trace.traces.iter_mut().zip(&txs) {
+            if let Some(meta) = trace.meta.as_mut() {
+                meta.hash = Some(tx.hash());
+                meta.block = Some(block);
+            }
+        }
+        Ok(trace)
+    }
+
+    /// Trace the transactions of the spec in order, each on the state left by the previous ones.
+    /// The state is modified as the transactions are committed.
+    pub fn trace<S: BcState>(
+        &self,
+        state: &mut S,
+        spec: TransitionSpec,
+    ) -> Result<BlockTrace, SoflError>
+    where
+        S::Error: Debug,
+    {
+        let mut tracer =
+            TxTracer::new(self.config).with_registry(self.registry.clone());
+        if let Some(selectors) = &self.selectors {
+            tracer = tracer.with_selectors(selectors.clone());
+        }
+        let block = spec.block.number.saturating_to();
+        let TransitionSpec {
+            cfg,
+            block: env,
+            txs,
+        } = spec;
+        let mut diffs = Vec::with_capacity(txs.len());
+        for (index, tx) in txs.into_iter().enumerate() {
+            let spec = TransitionSpec {
+                cfg: cfg.clone(),
+                block: env.clone(),
+                txs: vec![tx],
+            };
+            if self.state_diff || self.valuator.is_some() {
+                // keep the state before the transaction until it is diffed and priced
+                let (mut changes, _) = state.simulate(spec, &mut tracer)?;
+                let changes = changes.pop().unwrap_or_default();
+                let diff = if self.state_diff {
+                    Some(StateDiff::new(state, &changes)?)
+                } else {
+                    None
+                };
+                diffs.push(diff);
+                if let (Some(valuator), Some(trace)) =
+                    (&self.valuator, tracer.traces.last_mut())
+                {
+                    let position = TxPosition::new(block, index as u64);
+                    valuator.annotate_flows(
+                        state,
+                        position,
+                        &mut trace.money_flows,
+                    );
+                }
+                state.commit(changes);
+            } else {
+                state.transit(spec, &mut tracer)?;
+                diffs.push(None);
+            }
+        }
+
+        let mut traces = tracer.into_traces();
+        for (trace, diff) in traces.iter_mut().zip(diffs) {
+            trace.state_diff = diff;
+        }
+        Ok(BlockTrace {
+            summary: BlockSummary::new(block, &traces),
+            traces,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_dyn_abi::JsonAbiExt;
+    use alloy_json_abi::Function;
+    use libsofl_core::engine::{
+        memory::MemoryBcState,
+        transition::TransitionSpecBuilder,
+        types::{Address, TransactTo, TxEnv, B256, U256},
+    };
+    use libsofl_utils::solidity::scripting::deploy_contracts;
+
+    use crate::inspectors::tx_tracer::TraceConfig;
+
+    use super::BlockTracer;
+
+    #[test]
+    fn test_trace_block() {
+        let mut state = MemoryBcState::fresh();
+        let counter = deploy_contracts(
+            &mut state,
+            "0.8.12",
+            r#"
+        contract Counter {
+            event Count(uint256 count);
+            uint256 public count;
+            function inc() public {
+                count += 1;
+                emit Count(count);
+            }
+            function fail() public pure {
+                revert("fail");
+            }
+        }
+        "#,
+            vec!["Counter"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let tx = |f: &str| {
+            let mut tx = TxEnv::default();
+            tx.caller = Address::with_last_byte(9);
+            tx.transact_to = TransactTo::Call(counter);
+            tx.data = Function::parse(f)
+                .unwrap()
+                .abi_encode_input(&[])
+                .unwrap()
+                .into();
+            tx.gas_limit = 1_000_000;
+            tx
+        };
+        let spec = TransitionSpecBuilder::default()
+            .bypass_check()
+            .append_tx_env(tx("inc()"))
+            .append_tx_env(tx("fail()"))
+            .append_tx_env(tx("inc()"))
+            .build();
+
+        let tracer =
+            BlockTracer::new(TraceConfig::default()).with_state_diff(true);
+        let block = tracer.trace(&mut state, spec).unwrap();
+
+        let summary = &block.summary;
+        assert_eq!(summary.txs, 3);
+        assert_eq!((summary.succeeded, summary.failed), (2, 1));
+        assert_eq!(summary.logs, 2);
+        assert_eq!(summary.calls, 3);
+        assert_eq!(
+            summary.gas_used,
+            block
+                .traces
+                .iter()
+                .map(|t| t.meta.as_ref().unwrap().gas_used)
+                .sum::<u64>()
+        );
+
+        // each transaction sees the state left by the previous ones
+        let counts: Vec<_> = block
+            .traces
+            .iter()
+            .map(|t| {
+                t.state_diff
+                    .as_ref()
+                    .unwrap()
+                    .post
+                    .get(&counter)
+                    .and_then(|a| a.storage.values().next().copied())
+            })
+            .collect();
+        let word = |n: u64| Some(B256::from(U256::from(n)));
+        assert_eq!(counts, vec![word(1), None, word(2)]);
+        let indices: Vec<_> = block
+            .traces
+            .iter()
+            .map(|t| (t.meta.as_ref().unwrap().index, t.calls[0].index))
+            .collect();
+        assert_eq!(indices, vec![(0, 1), (1, 1), (2, 1)]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use libsofl_core::{engine::types::TxHash, error::SoflError};
+
+use crate::trace::{MoneyFlow, TxTrace};
+
+use super::{io_err, DatasetWriter, SCHEMA_VERSION};
+
+const HEADER: &str = "schema_version,tx,index,from,to,kind,action,event,token,amount,value,token_id,reverted,call_path";
+
+/// Appends the money flows of each transaction as edges to `<dir>/money_flows.csv`.
+/// The call path is joined by `/`.
+pub struct CsvWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl CsvWriter {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, SoflError> {
+        let path = dir.as_ref().join("money_flows.csv");
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io_err(&path, e))?;
+        let is_empty =
+            file.metadata().map_err(|e| io_err(&path, e))?.len() == 0;
+        let mut file = BufWriter::new(file);
+        if is_empty {
+            writeln!(file, "{}", HEADER).map_err(|e| io_err(&path, e))?;
+        }
+        Ok(Self { path, file })
+    }
+
+    fn row(tx: TxHash, flow: &MoneyFlow) -> String {
+        let path: Vec<String> =
+            flow.call_path.iter().map(|i| i.to_string()).collect();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            SCHEMA_VERSION,
+            tx,
+            flow.index,
+            flow.from,
+            flow.to,
+            flow.kind.name(),
+            flow.action.name(),
+            flow.event.as_deref().unwrap_or_default(),
+            flow.token.map(|t| t.to_string()).unwrap_or_default(),
+            flow.amount,
+            flow.value.map(|v| v.to_string()).unwrap_or_default(),
+            flow.token_id.map(|id| id.to_string()).unwrap_or_default(),
+            flow.reverted,
+            path.join("/"),
+        )
+    }
+}
+
+impl DatasetWriter for CsvWriter {
+    fn write(&mut self, tx: TxHash, trace: &TxTrace) -> Result<(), SoflError> {
+        for flow in &trace.money_flows {
+            writeln!(self.file, "{}", Self::row(tx, flow))
+                .map_err(|e| io_err(&self.path, e))?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SoflError> {
+        self.file.flush().map_err(|e| io_err(&self.path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{TxHash, U256},
+    };
+
+    use crate::trace::MoneyFlow;
+
+    use super::{CsvWriter, HEADER};
+
+    #[test]
+    fn test_money_flow_row() {
+        let flow =
+            MoneyFlow::ether(1, 0x1.cvt(), 0x2.cvt(), U256::MAX, vec![1, 3]);
+        let row = CsvWriter::row(TxHash::ZERO, &flow);
+        let columns: Vec<&str> = row.split(',').collect();
+        assert_eq!(columns.len(), HEADER.split(',').count());
+        assert_eq!(columns[5], "ETH");
+        assert_eq!(columns[8], "");
+        assert_eq!(columns[9], U256::MAX.to_string());
+        assert_eq!(columns[10], "");
+        assert_eq!(columns[13], "1/3");
+    }
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libsofl_core::{engine::types::TxHash, error::SoflError};
+
+use crate::trace::TxTrace;
+
+use super::{io_err, DatasetWriter, TxRecord};
+
+/// Writes the trace of each transaction into `<dir>/<tx hash>.json`.
+pub struct JsonWriter {
+    dir: PathBuf,
+}
+
+impl JsonWriter {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl DatasetWriter for JsonWriter {
+    fn write(&mut self, tx: TxHash, trace: &TxTrace) -> Result<(), SoflError> {
+        let path = self.dir.join(format!("{}.json", tx));
+        let json = serde_json::to_string(&TxRecord::new(tx, trace))
+            .map_err(|e| io_err(&path, e))?;
+
+        // write to a temporary file first, so that interrupted writes leave no partial file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .map_err(|e| io_err(&path, e))
+    }
+
+    fn flush(&mut self) -> Result<(), SoflError> {
+        Ok(())
+    }
+}
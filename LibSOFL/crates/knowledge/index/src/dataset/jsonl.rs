@@ -0,0 +1,46 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use libsofl_core::{engine::types::TxHash, error::SoflError};
+
+use crate::trace::TxTrace;
+
+use super::{io_err, DatasetWriter, TxRecord};
+
+/// Appends the trace of each transaction as a line of `<dir>/traces.jsonl`.
+pub struct JsonlWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl JsonlWriter {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, SoflError> {
+        let path = dir.as_ref().join("traces.jsonl");
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io_err(&path, e))?;
+        Ok(Self {
+            path,
+            file: BufWriter::new(file),
+        })
+    }
+}
+
+impl DatasetWriter for JsonlWriter {
+    fn write(&mut self, tx: TxHash, trace: &TxTrace) -> Result<(), SoflError> {
+        serde_json::to_writer(&mut self.file, &TxRecord::new(tx, trace))
+            .map_err(|e| io_err(&self.path, e))?;
+        self.file
+            .write_all(b"\n")
+            .map_err(|e| io_err(&self.path, e))
+    }
+
+    fn flush(&mut self) -> Result<(), SoflError> {
+        self.file.flush().map_err(|e| io_err(&self.path, e))
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use libsofl_core::{engine::types::TxHash, error::SoflError};
+use serde::{Deserialize, Serialize};
+
+use crate::trace::TxTrace;
+
+pub mod csv;
+pub mod json;
+pub mod jsonl;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Version of the dataset schema, bumped whenever the output layout changes.
+pub const SCHEMA_VERSION: u32 = 8;
+
+/// Writer of transaction traces into a dataset.
+pub trait DatasetWriter: Send {
+    /// Write the trace of a transaction. It may be buffered until `flush`.
+    fn write(&mut self, tx: TxHash, trace: &TxTrace) -> Result<(), SoflError>;
+
+    /// Persist all written traces.
+    fn flush(&mut self) -> Result<(), SoflError>;
+}
+
+/// A transaction trace in the dataset.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxRecord<T = TxTrace> {
+    pub schema_version: u32,
+    pub tx: TxHash,
+    #[serde(flatten)]
+    pub trace: T,
+}
+
+impl<'a> TxRecord<&'a TxTrace> {
+    pub fn new(tx: TxHash, trace: &'a TxTrace) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tx,
+            trace,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// One JSON file per transaction.
+    #[default]
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+    /// Edge list of money flows.
+    Csv,
+    /// Columnar tables of transactions, money flows, calls, opcodes and storage diffs.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl FromStr for DatasetFormat {
+    type Err = SoflError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(DatasetFormat::Json),
+            "jsonl" => Ok(DatasetFormat::Jsonl),
+            "csv" => Ok(DatasetFormat::Csv),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(DatasetFormat::Parquet),
+            _ => Err(SoflError::Unsupported(format!("dataset format {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for DatasetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DatasetFormat::Json => "json",
+            DatasetFormat::Jsonl => "jsonl",
+            DatasetFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            DatasetFormat::Parquet => "parquet",
+        };
+        f.write_str(name)
+    }
+}
+
+impl DatasetFormat {
+    /// Open a writer of the format in the directory.
+    pub fn open(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Box<dyn DatasetWriter>, SoflError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| io_err(dir, e))?;
+        Ok(match self {
+            DatasetFormat::Json => Box::new(json::JsonWriter::new(dir)),
+            DatasetFormat::Jsonl => Box::new(jsonl::JsonlWriter::open(dir)?),
+            DatasetFormat::Csv => Box::new(csv::CsvWriter::open(dir)?),
+            #[cfg(feature = "parquet")]
+            DatasetFormat::Parquet => {
+                Box::new(parquet::ParquetWriter::new(dir))
+            }
+        })
+    }
+}
+
+pub(crate) fn io_err(path: &Path, e: impl fmt::Display) -> SoflError {
+    SoflError::Custom(format!("failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,517 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ::parquet::{
+    arrow::ArrowWriter,
+    file::{metadata::KeyValue, properties::WriterProperties},
+};
+use arrow::{
+    array::{
+        ArrayBuilder, ArrayRef, BooleanBuilder, ListBuilder, StringBuilder,
+        UInt32Builder, UInt64Builder,
+    },
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use libsofl_core::{engine::types::TxHash, error::SoflError};
+
+use crate::trace::TxTrace;
+
+use super::{io_err, DatasetWriter, SCHEMA_VERSION};
+
+/// Writes columnar tables of transactions, money flows, calls, opcodes and storage diffs.
+/// Each flush writes complete files `<dir>/<table>-<run>-<part>.parquet`,
+/// where `run` is the time the writer is created, so that flushed data are readable
+/// even if the process is interrupted later.
+/// Integers beyond 64 bits (amounts, values) are stored as decimal strings.
+/// Traces must carry their transaction metadata, as every transaction has a row.
+pub struct ParquetWriter {
+    dir: PathBuf,
+    run: u128,
+    part: usize,
+    txs: TxColumns,
+    flows: FlowColumns,
+    calls: CallColumns,
+    opcodes: OpcodeColumns,
+    storage_diffs: StorageDiffColumns,
+}
+
+impl ParquetWriter {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        let run = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            run,
+            part: 0,
+            txs: Default::default(),
+            flows: Default::default(),
+            calls: Default::default(),
+            opcodes: Default::default(),
+            storage_diffs: Default::default(),
+        }
+    }
+
+    fn write_table(
+        &self,
+        table: &str,
+        schema: SchemaRef,
+        columns: Vec<ArrayRef>,
+    ) -> Result<(), SoflError> {
+        let path = self
+            .dir
+            .join(format!("{}-{}-{}.parquet", table, self.run, self.part));
+        let batch = RecordBatch::try_new(schema.clone(), columns)
+            .map_err(|e| io_err(&path, e))?;
+        let props = WriterProperties::builder()
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                "schema_version".to_string(),
+                SCHEMA_VERSION.to_string(),
+            )]))
+            .build();
+        let file = File::create(&path).map_err(|e| io_err(&path, e))?;
+        let mut writer = ArrowWriter::try_new(file, schema, Some(props))
+            .map_err(|e| io_err(&path, e))?;
+        writer.write(&batch).map_err(|e| io_err(&path, e))?;
+        writer.close().map_err(|e| io_err(&path, e))?;
+        Ok(())
+    }
+}
+
+impl DatasetWriter for ParquetWriter {
+    fn write(&mut self, tx: TxHash, trace: &TxTrace) -> Result<(), SoflError> {
+        // check before appending anything, so that the tables stay consistent
+        let Some(meta) = &trace.meta else {
+            return Err(SoflError::Custom(format!(
+                "trace of {} has no transaction metadata",
+                tx
+            )));
+        };
+        let tx = tx.to_string();
+        let c = &mut self.txs;
+        c.tx.append_value(&tx);
+        c.block.append_option(meta.block);
+        c.caller.append_value(meta.caller.to_string());
+        c.to.append_option(meta.to.map(|to| to.to_string()));
+        c.value.append_value(meta.value.to_string());
+        c.gas_limit.append_value(meta.gas_limit);
+        c.gas_used.append_value(meta.gas_used);
+        c.success.append_value(meta.success);
+        c.logs.append_value(meta.logs as u64);
+        for flow in &trace.money_flows {
+            let c = &mut self.flows;
+            c.tx.append_value(&tx);
+            c.index.append_value(flow.index as u64);
+            c.from.append_value(flow.from.to_string());
+            c.to.append_value(flow.to.to_string());
+            c.kind.append_value(flow.kind.name());
+            c.action.append_value(flow.action.name());
+            c.event.append_option(flow.event.as_deref());
+            c.token.append_option(flow.token.map(|t| t.to_string()));
+            c.amount.append_value(flow.amount.to_string());
+            c.value.append_option(flow.value.map(|v| v.to_string()));
+            c.token_id
+                .append_option(flow.token_id.map(|id| id.to_string()));
+            c.reverted.append_value(flow.reverted);
+            c.call_path
+                .append_value(flow.call_path.iter().map(|i| Some(*i as u64)));
+        }
+        for call in &trace.calls {
+            let c = &mut self.calls;
+            c.tx.append_value(&tx);
+            c.index.append_value(call.index as u64);
+            c.parent.append_value(call.parent as u64);
+            c.kind.append_value(call.kind.name());
+            c.from.append_value(call.from.to_string());
+            c.to.append_value(call.to.to_string());
+            c.function.append_option(call.function());
+            c.input.append_value(call.input.to_string());
+            c.output.append_value(call.output.to_string());
+            c.value.append_value(call.value.to_string());
+            c.result.append_value(format!("{:?}", call.result));
+            c.revert_reason.append_option(call.revert_reason.as_deref());
+            c.gas_used.append_value(call.gas_used);
+            c.reverted.append_value(call.reverted);
+        }
+        let call_indices = trace.opcode_call_indices();
+        for (i, op) in trace.opcodes.iter().enumerate() {
+            let c = &mut self.opcodes;
+            c.tx.append_value(&tx);
+            c.index.append_value(i as u64);
+            c.call_index.append_value(call_indices[i] as u64);
+            c.op.append_value(op.mnemonic());
+            c.pc.append_value(op.pc);
+            c.gas_remaining.append_value(op.gas_remaining);
+            c.depth.append_value(op.depth);
+        }
+        if let Some(diff) = &trace.state_diff {
+            for (address, slot, old, new) in diff.storage_changes() {
+                let c = &mut self.storage_diffs;
+                c.tx.append_value(&tx);
+                c.address.append_value(address.to_string());
+                c.slot.append_value(slot.to_string());
+                c.old.append_value(old.to_string());
+                c.new.append_value(new.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SoflError> {
+        if !self.txs.tx.is_empty() {
+            let columns = self.txs.finish();
+            self.write_table("transactions", TxColumns::schema(), columns)?;
+        }
+        if !self.flows.tx.is_empty() {
+            let columns = self.flows.finish();
+            self.write_table("money_flows", FlowColumns::schema(), columns)?;
+        }
+        if !self.calls.tx.is_empty() {
+            let columns = self.calls.finish();
+            self.write_table("calls", CallColumns::schema(), columns)?;
+        }
+        if !self.opcodes.tx.is_empty() {
+            let columns = self.opcodes.finish();
+            self.write_table("opcodes", OpcodeColumns::schema(), columns)?;
+        }
+        if !self.storage_diffs.tx.is_empty() {
+            let columns = self.storage_diffs.finish();
+            self.write_table(
+                "storage_diffs",
+                StorageDiffColumns::schema(),
+                columns,
+            )?;
+        }
+        self.part += 1;
+        Ok(())
+    }
+}
+
+fn call_path_field() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::UInt64, true)))
+}
+
+#[derive(Default)]
+struct TxColumns {
+    tx: StringBuilder,
+    block: UInt64Builder,
+    caller: StringBuilder,
+    to: StringBuilder,
+    value: StringBuilder,
+    gas_limit: UInt64Builder,
+    gas_used: UInt64Builder,
+    success: BooleanBuilder,
+    logs: UInt64Builder,
+}
+
+impl TxColumns {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tx", DataType::Utf8, false),
+            Field::new("block", DataType::UInt64, true),
+            Field::new("caller", DataType::Utf8, false),
+            Field::new("to", DataType::Utf8, true),
+            Field::new("value", DataType::Utf8, false),
+            Field::new("gas_limit", DataType::UInt64, false),
+            Field::new("gas_used", DataType::UInt64, false),
+            Field::new("success", DataType::Boolean, false),
+            Field::new("logs", DataType::UInt64, false),
+        ]))
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.tx.finish()),
+            Arc::new(self.block.finish()),
+            Arc::new(self.caller.finish()),
+            Arc::new(self.to.finish()),
+            Arc::new(self.value.finish()),
+            Arc::new(self.gas_limit.finish()),
+            Arc::new(self.gas_used.finish()),
+            Arc::new(self.success.finish()),
+            Arc::new(self.logs.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct FlowColumns {
+    tx: StringBuilder,
+    index: UInt64Builder,
+    from: StringBuilder,
+    to: StringBuilder,
+    kind: StringBuilder,
+    action: StringBuilder,
+    event: StringBuilder,
+    token: StringBuilder,
+    amount: StringBuilder,
+    value: StringBuilder,
+    token_id: StringBuilder,
+    reverted: BooleanBuilder,
+    call_path: ListBuilder<UInt64Builder>,
+}
+
+impl FlowColumns {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tx", DataType::Utf8, false),
+            Field::new("index", DataType::UInt64, false),
+            Field::new("from", DataType::Utf8, false),
+            Field::new("to", DataType::Utf8, false),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("action", DataType::Utf8, false),
+            Field::new("event", DataType::Utf8, true),
+            Field::new("token", DataType::Utf8, true),
+            Field::new("amount", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, true),
+            Field::new("token_id", DataType::Utf8, true),
+            Field::new("reverted", DataType::Boolean, false),
+            Field::new("call_path", call_path_field(), false),
+        ]))
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.tx.finish()),
+            Arc::new(self.index.finish()),
+            Arc::new(self.from.finish()),
+            Arc::new(self.to.finish()),
+            Arc::new(self.kind.finish()),
+            Arc::new(self.action.finish()),
+            Arc::new(self.event.finish()),
+            Arc::new(self.token.finish()),
+            Arc::new(self.amount.finish()),
+            Arc::new(self.value.finish()),
+            Arc::new(self.token_id.finish()),
+            Arc::new(self.reverted.finish()),
+            Arc::new(self.call_path.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct CallColumns {
+    tx: StringBuilder,
+    index: UInt64Builder,
+    parent: UInt64Builder,
+    kind: StringBuilder,
+    from: StringBuilder,
+    to: StringBuilder,
+    function: StringBuilder,
+    input: StringBuilder,
+    output: StringBuilder,
+    value: StringBuilder,
+    result: StringBuilder,
+    revert_reason: StringBuilder,
+    gas_used: UInt64Builder,
+    reverted: BooleanBuilder,
+}
+
+impl CallColumns {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tx", DataType::Utf8, false),
+            Field::new("index", DataType::UInt64, false),
+            Field::new("parent", DataType::UInt64, false),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("from", DataType::Utf8, false),
+            Field::new("to", DataType::Utf8, false),
+            Field::new("function", DataType::Utf8, true),
+            Field::new("input", DataType::Utf8, false),
+            Field::new("output", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, false),
+            Field::new("result", DataType::Utf8, false),
+            Field::new("revert_reason", DataType::Utf8, true),
+            Field::new("gas_used", DataType::UInt64, false),
+            Field::new("reverted", DataType::Boolean, false),
+        ]))
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.tx.finish()),
+            Arc::new(self.index.finish()),
+            Arc::new(self.parent.finish()),
+            Arc::new(self.kind.finish()),
+            Arc::new(self.from.finish()),
+            Arc::new(self.to.finish()),
+            Arc::new(self.function.finish()),
+            Arc::new(self.input.finish()),
+            Arc::new(self.output.finish()),
+            Arc::new(self.value.finish()),
+            Arc::new(self.result.finish()),
+            Arc::new(self.revert_reason.finish()),
+            Arc::new(self.gas_used.finish()),
+            Arc::new(self.reverted.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct OpcodeColumns {
+    tx: StringBuilder,
+    index: UInt64Builder,
+    call_index: UInt64Builder,
+    op: StringBuilder,
+    pc: UInt32Builder,
+    gas_remaining: UInt64Builder,
+    depth: UInt32Builder,
+}
+
+impl OpcodeColumns {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tx", DataType::Utf8, false),
+            Field::new("index", DataType::UInt64, false),
+            Field::new("call_index", DataType::UInt64, false),
+            Field::new("op", DataType::Utf8, false),
+            Field::new("pc", DataType::UInt32, false),
+            Field::new("gas_remaining", DataType::UInt64, false),
+            Field::new("depth", DataType::UInt32, false),
+        ]))
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.tx.finish()),
+            Arc::new(self.index.finish()),
+            Arc::new(self.call_index.finish()),
+            Arc::new(self.op.finish()),
+            Arc::new(self.pc.finish()),
+            Arc::new(self.gas_remaining.finish()),
+            Arc::new(self.depth.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct StorageDiffColumns {
+    tx: StringBuilder,
+    address: StringBuilder,
+    slot: StringBuilder,
+    old: StringBuilder,
+    new: StringBuilder,
+}
+
+impl StorageDiffColumns {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tx", DataType::Utf8, false),
+            Field::new("address", DataType::Utf8, false),
+            Field::new("slot", DataType::Utf8, false),
+            Field::new("old", DataType::Utf8, false),
+            Field::new("new", DataType::Utf8, false),
+        ]))
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.tx.finish()),
+            Arc::new(self.address.finish()),
+            Arc::new(self.slot.finish()),
+            Arc::new(self.old.finish()),
+            Arc::new(self.new.finish()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use arrow::{
+        array::{Array, AsArray},
+        datatypes::UInt64Type,
+        record_batch::RecordBatch,
+    };
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{TransactTo, TxEnv, TxHash, U256},
+    };
+
+    use crate::{
+        dataset::DatasetWriter,
+        trace::{MoneyFlow, TxMeta, TxTrace},
+    };
+
+    use super::ParquetWriter;
+
+    fn read(dir: &std::path::Path, table: &str) -> RecordBatch {
+        let path = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| {
+                p.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with(&format!("{}-", table))
+            })
+            .unwrap();
+        let mut reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap();
+        reader.next().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        let mut tx = TxEnv::default();
+        tx.transact_to = TransactTo::Call(0x2.cvt());
+        tx.gas_limit = 21000;
+        let mut trace = TxTrace {
+            meta: Some(TxMeta::new(0, &tx)),
+            money_flows: vec![MoneyFlow::ether(
+                1,
+                0x1.cvt(),
+                0x2.cvt(),
+                U256::MAX,
+                vec![1, 3],
+            )],
+            ..Default::default()
+        };
+        let mut writer = ParquetWriter::new(dir);
+        writer.write(TxHash::ZERO, &trace).unwrap();
+        writer.flush().unwrap();
+
+        let txs = read(dir, "transactions");
+        assert_eq!(txs.num_rows(), 1);
+        assert_eq!(
+            txs.column(0).as_string::<i32>().value(0),
+            TxHash::ZERO.to_string()
+        );
+        assert!(txs.column(1).is_null(0));
+        assert_eq!(txs.column(5).as_primitive::<UInt64Type>().value(0), 21000);
+
+        let flows = read(dir, "money_flows");
+        assert_eq!(flows.num_rows(), 1);
+        assert_eq!(flows.column(4).as_string::<i32>().value(0), "ETH");
+        assert_eq!(
+            flows.column(8).as_string::<i32>().value(0),
+            U256::MAX.to_string()
+        );
+        assert!(flows.column(9).is_null(0));
+        let call_path = flows.column(12).as_list::<i32>().value(0);
+        assert_eq!(
+            call_path.as_primitive::<UInt64Type>().values().to_vec(),
+            vec![1, 3]
+        );
+
+        // a trace without metadata is rejected rather than losing its transaction row
+        trace.meta = None;
+        assert!(writer.write(TxHash::ZERO, &trace).is_err());
+        writer.flush().unwrap();
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,417 @@
+//! Net asset changes of the addresses involved in a transaction, computed from its money flows.
+
+use std::{collections::HashMap, fmt::Debug};
+
+use libsofl_core::{
+    engine::{
+        state::BcState,
+        types::{Address, BlockEnv, StateChange, TxEnv, I256, U256},
+    },
+    error::SoflError,
+};
+use libsofl_periphery::cheatcodes::CheatCodes;
+use libsofl_utils::log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::trace::{Asset, MoneyFlow, TokenKind};
+
+/// Amounts of an asset received and sent by an address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetDelta {
+    pub address: Address,
+    pub asset: Asset,
+    pub received: U256,
+    pub sent: U256,
+    /// Value of the net change in wei, if the asset is priced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<I256>,
+}
+
+impl AssetDelta {
+    /// Net change of the balance, saturated at the bounds of `I256`.
+    pub fn net(&self) -> I256 {
+        signed_diff(self.received, self.sent)
+    }
+}
+
+/// A balance change that does not match the money flows,
+/// e.g., caused by fee-on-transfer or rebasing tokens, or untracked transfers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discrepancy {
+    pub address: Address,
+    pub asset: Asset,
+    /// Net change according to the money flows (and gas fees for ether).
+    pub expected: I256,
+    /// Net change of the balance in the state.
+    pub actual: I256,
+}
+
+/// Gas fee paid by the sender of a transaction and the part of it received by the coinbase.
+/// The rest, i.e., the base fee, is burnt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasFees {
+    pub sender: Address,
+    pub coinbase: Address,
+    pub fee: U256,
+    pub tip: U256,
+}
+
+impl GasFees {
+    pub fn new(tx: &TxEnv, block: &BlockEnv, gas_used: u64) -> Self {
+        let price = match tx.gas_priority_fee {
+            Some(priority_fee) => {
+                tx.gas_price.min(block.basefee.saturating_add(priority_fee))
+            }
+            None => tx.gas_price,
+        };
+        let gas_used = U256::from(gas_used);
+        Self {
+            sender: tx.caller,
+            coinbase: block.coinbase,
+            fee: price.saturating_mul(gas_used),
+            tip: price.saturating_sub(block.basefee).saturating_mul(gas_used),
+        }
+    }
+}
+
+/// Net asset changes per address and per asset in a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetDeltas {
+    /// Changes in order of first appearance in the money flows.
+    pub deltas: Vec<AssetDelta>,
+    /// Mismatches found by the cross checks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl AssetDeltas {
+    /// Sum up the committed money flows, ignoring reverted ones.
+    /// Mints and burns show up as changes of the zero address.
+    /// A flow repeating the previous one through another event of the same token,
+    /// e.g., ERC777 `Sent` next to `Transfer`, is counted once.
+    pub fn from_flows<'a>(
+        flows: impl IntoIterator<Item = &'a MoneyFlow>,
+    ) -> Self {
+        let mut deltas = Self::default();
+        let mut positions = HashMap::new();
+        let mut previous: Option<&MoneyFlow> = None;
+        for flow in flows.into_iter().filter(|f| !f.reverted) {
+            let duplicated = previous.is_some_and(|p| is_duplicate(p, flow));
+            previous = Some(flow);
+            if duplicated {
+                continue;
+            }
+            let asset = flow.asset();
+            for (address, received) in [(flow.from, false), (flow.to, true)] {
+                let i =
+                    *positions.entry((address, asset)).or_insert_with(|| {
+                        deltas.deltas.push(AssetDelta {
+                            address,
+                            asset,
+                            received: U256::ZERO,
+                            sent: U256::ZERO,
+                            value: None,
+                        });
+                        deltas.deltas.len() - 1
+                    });
+                let delta = &mut deltas.deltas[i];
+                if received {
+                    delta.received = delta.received.saturating_add(flow.amount);
+                } else {
+                    delta.sent = delta.sent.saturating_add(flow.amount);
+                }
+            }
+        }
+        deltas
+    }
+
+    pub fn get(&self, address: Address, asset: &Asset) -> Option<&AssetDelta> {
+        self.deltas
+            .iter()
+            .find(|d| d.address == address && &d.asset == asset)
+    }
+
+    /// Net change of an asset of an address, zero if it is not involved.
+    pub fn net(&self, address: Address, asset: &Asset) -> I256 {
+        self.get(address, asset)
+            .map(|d| d.net())
+            .unwrap_or(I256::ZERO)
+    }
+
+    /// Total value in wei of the priced changes of each address, i.e., its profit,
+    /// in order of first appearance.
+    pub fn profits(&self) -> Vec<(Address, I256)> {
+        let mut profits: Vec<(Address, I256)> = Vec::new();
+        for delta in &self.deltas {
+            let Some(value) = delta.value else {
+                continue;
+            };
+            match profits.iter_mut().find(|(a, _)| *a == delta.address) {
+                Some((_, profit)) => *profit = profit.saturating_add(value),
+                None => profits.push((delta.address, value)),
+            }
+        }
+        profits
+    }
+
+    /// Compare the ether changes with the balances in the state changes returned by `BcState::simulate`.
+    /// `state` must be the state before the transaction.
+    /// Gas fees, if given, are added to the expected changes of the sender and the coinbase.
+    pub fn check_ether<S: BcState>(
+        &mut self,
+        state: &mut S,
+        changes: &StateChange,
+        fees: Option<&GasFees>,
+    ) -> Result<(), SoflError>
+    where
+        S::Error: Debug,
+    {
+        let mut expected: Vec<(Address, I256)> = self
+            .deltas
+            .iter()
+            .filter(|d| d.asset == Asset::ETH)
+            .map(|d| (d.address, d.net()))
+            .collect();
+        let mut add = |address: Address, amount: I256| match expected
+            .iter_mut()
+            .find(|(a, _)| *a == address)
+        {
+            Some((_, net)) => *net = net.saturating_add(amount),
+            None => expected.push((address, amount)),
+        };
+        if let Some(fees) = fees {
+            add(fees.sender, signed_diff(U256::ZERO, fees.fee));
+            add(fees.coinbase, signed_diff(fees.tip, U256::ZERO));
+        }
+        for address in changes.keys() {
+            add(*address, I256::ZERO);
+        }
+
+        let mut cheatcodes = CheatCodes::new();
+        for (address, expected) in expected {
+            let before = cheatcodes.get_balance(state, address)?;
+            let after = changes
+                .get(&address)
+                .map(|account| account.info.balance)
+                .unwrap_or(before);
+            let actual = signed_diff(after, before);
+            if actual != expected {
+                self.discrepancies.push(Discrepancy {
+                    address,
+                    asset: Asset::ETH,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare the changes of ERC20 and ERC777 tokens with `balanceOf` before and after the transaction.
+    /// `state` must be the state before the transaction, and it is left at the state after
+    /// the transaction by applying `changes`. Tokens whose balances cannot be queried are skipped.
+    pub fn check_tokens<S: BcState>(
+        &mut self,
+        state: &mut S,
+        changes: Vec<StateChange>,
+    ) -> Result<(), SoflError>
+    where
+        S::Error: Debug,
+    {
+        let fungible: Vec<(Asset, Address, I256)> = self
+            .deltas
+            .iter()
+            .filter(|d| {
+                matches!(d.asset.kind, TokenKind::Erc20 | TokenKind::Erc777)
+                    && d.asset.token.is_some()
+                    && !d.address.is_zero()
+            })
+            .map(|d| (d.asset, d.address, d.net()))
+            .collect();
+        if fungible.is_empty() {
+            return Ok(());
+        }
+
+        let mut cheatcodes = CheatCodes::new();
+        let mut balance = |state: &mut S, asset: Asset, account: Address| {
+            let token = asset.token.expect("bug: ether is not a token");
+            cheatcodes
+                .get_erc20_balance(state, token, account)
+                .map_err(|e| {
+                    debug!(token = %token, err = %e, "failed to query balance");
+                })
+                .ok()
+        };
+        let before: Vec<Option<U256>> = fungible
+            .iter()
+            .map(|(asset, account, _)| balance(state, *asset, *account))
+            .collect();
+        state.apply_changes(changes);
+        for ((asset, address, expected), before) in
+            fungible.into_iter().zip(before)
+        {
+            let Some(before) = before else {
+                continue;
+            };
+            let Some(after) = balance(state, asset, address) else {
+                continue;
+            };
+            let actual = signed_diff(after, before);
+            if actual != expected {
+                self.discrepancies.push(Discrepancy {
+                    address,
+                    asset,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the token flow is the same movement as the one right before it,
+/// carried by a different event of the same token in the same call frame.
+fn is_duplicate(previous: &MoneyFlow, flow: &MoneyFlow) -> bool {
+    flow.token.is_some()
+        && flow.index == previous.index + 1
+        && flow.event != previous.event
+        && flow.token == previous.token
+        && flow.token_id == previous.token_id
+        && flow.from == previous.from
+        && flow.to == previous.to
+        && flow.amount == previous.amount
+        && flow.call_path == previous.call_path
+}
+
+/// `a - b`, saturated at the bounds of `I256`.
+pub(crate) fn signed_diff(a: U256, b: U256) -> I256 {
+    if a >= b {
+        I256::try_from(a - b).unwrap_or(I256::MAX)
+    } else {
+        I256::try_from(b - a).map(|d| -d).unwrap_or(I256::MIN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::{
+            memory::MemoryBcState,
+            state::BcState,
+            transition::TransitionSpecBuilder,
+            types::{
+                AccountInfo, Address, BlockEnv, Bytecode, ExecutionResult,
+                TransactTo, TxEnv, I256, U256,
+            },
+        },
+    };
+
+    use crate::{
+        inspectors::tx_tracer::{TraceConfig, TxTracer},
+        testing::fixtures::{vault_deposit, VaultDeposit},
+        trace::Asset,
+    };
+
+    use super::{AssetDeltas, GasFees};
+
+    #[test]
+    fn test_ether_deltas_with_gas_fees() {
+        let sender: Address = 0x1.cvt();
+        let receiver: Address = 0x2.cvt();
+        let coinbase: Address = 0x3.cvt();
+
+        let mut state = MemoryBcState::fresh();
+        state.insert_account_info(
+            sender,
+            AccountInfo::new(
+                U256::from(10).pow(U256::from(18)),
+                0,
+                Default::default(),
+                Bytecode::new(),
+            ),
+        );
+        let block = BlockEnv {
+            coinbase,
+            gas_limit: U256::from(1000000),
+            ..Default::default()
+        };
+        let mut tx = TxEnv::default();
+        tx.caller = sender;
+        tx.transact_to = TransactTo::Call(receiver);
+        tx.value = U256::from(500);
+        tx.gas_limit = 100000;
+        tx.gas_price = U256::from(7);
+        let spec = TransitionSpecBuilder::new()
+            .set_block(block.clone())
+            .append_tx_env(tx.clone())
+            .build();
+
+        let mut tracer = TxTracer::new(TraceConfig::default());
+        let (changes, results) = state.simulate(spec, &mut tracer).unwrap();
+        let ExecutionResult::Success { gas_used, .. } = results[0] else {
+            panic!("transfer failed");
+        };
+        let trace = tracer.into_trace();
+
+        let deltas = AssetDeltas::from_flows(&trace.money_flows);
+        assert_eq!(deltas.deltas.len(), 2);
+        assert_eq!(
+            deltas.net(sender, &Asset::ETH),
+            I256::try_from(-500i64).unwrap()
+        );
+        assert_eq!(
+            deltas.net(receiver, &Asset::ETH),
+            I256::try_from(500i64).unwrap()
+        );
+
+        // gas fees are not money flows
+        let mut unchecked = deltas.clone();
+        unchecked
+            .check_ether(&mut state, &changes[0], None)
+            .unwrap();
+        let mut addresses: Vec<Address> =
+            unchecked.discrepancies.iter().map(|d| d.address).collect();
+        addresses.sort();
+        assert_eq!(addresses, vec![sender, coinbase]);
+
+        let mut checked = deltas;
+        let fees = GasFees::new(&tx, &block, gas_used);
+        assert_eq!(fees.fee, U256::from(7 * gas_used));
+        checked
+            .check_ether(&mut state, &changes[0], Some(&fees))
+            .unwrap();
+        assert!(checked.discrepancies.is_empty());
+    }
+
+    #[test]
+    fn test_check_tokens_vault_deposit() {
+        let VaultDeposit {
+            mut state,
+            changes,
+            trace,
+            main,
+        } = vault_deposit();
+        let flows = &trace.money_flows;
+        let deltas = AssetDeltas::from_flows(flows);
+        let asset = flows[0].asset();
+        let shares = flows[2].asset();
+        let vault = flows[1].to;
+        assert_eq!(deltas.net(main, &asset), I256::ZERO);
+        assert_eq!(deltas.net(vault, &asset), I256::try_from(100i64).unwrap());
+        assert_eq!(deltas.net(main, &shares), I256::try_from(50i64).unwrap());
+
+        // the same share mint carried by another event right after `Transfer`
+        let mut duplicated = flows.clone();
+        let mut deposit = flows[2].clone();
+        deposit.index += 1;
+        deposit.event = Some("Deposit".to_string());
+        duplicated.push(deposit);
+        assert_eq!(AssetDeltas::from_flows(&duplicated), deltas);
+
+        let mut checked = deltas;
+        checked.check_tokens(&mut state, changes).unwrap();
+        assert!(checked.discrepancies.is_empty());
+    }
+}
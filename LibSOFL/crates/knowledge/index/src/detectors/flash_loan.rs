@@ -0,0 +1,250 @@
+use libsofl_core::engine::types::{Address, U256};
+
+use crate::trace::{CallTrace, MoneyFlow, TxTrace};
+
+use super::{ancestors, in_subtree, is_fungible, Detector, Finding};
+
+/// Detects flash loans: a lender sends an asset to a borrower, either itself or through a
+/// reserve contract it calls (e.g., Aave's aTokens), calls back into the borrower, and gets
+/// at least the same amount of the asset back into the lender or the reserve before it returns.
+/// This covers Balancer, dYdX, Aave and ERC3156 lenders. Loans repaid in another asset,
+/// i.e., Uniswap flash swaps, are reported if the borrower calls other contracts than the lender
+/// and the tokens during the callback, so that swaps merely paid in a callback are not.
+pub struct FlashLoanDetector;
+
+impl FlashLoanDetector {
+    /// Whether the borrower calls a contract other than the lender, the reserve
+    /// and the tokens involved during the callback, i.e., it uses the loan.
+    fn uses_loan(
+        calls: &[CallTrace],
+        callback: &CallTrace,
+        excluded: &[Option<Address>],
+    ) -> bool {
+        calls.iter().any(|c| {
+            c.index != callback.index
+                && in_subtree(calls, callback.index, c.index)
+                && !excluded.contains(&Some(c.to))
+        })
+    }
+}
+
+impl Detector for FlashLoanDetector {
+    fn name(&self) -> &'static str {
+        "flash-loan"
+    }
+
+    fn detect(&self, trace: &TxTrace) -> Vec<Finding> {
+        let calls = &trace.calls;
+        let flows: Vec<&MoneyFlow> = trace
+            .money_flows
+            .iter()
+            .filter(|f| !f.reverted && is_fungible(f))
+            .collect();
+        let mut loans: Vec<usize> = Vec::new();
+        let mut findings = Vec::new();
+        for callback in
+            calls.iter().filter(|c| !c.reverted && !c.kind.is_create())
+        {
+            // the callback is initiated by the lender's own call frame
+            let (lender, borrower) = (callback.from, callback.to);
+            let Some(frame) = ancestors(calls, callback).next() else {
+                continue;
+            };
+            if frame.to != lender || lender == borrower {
+                continue;
+            }
+            // contracts called by the lender before the callback may hold the reserves
+            let reserves: Vec<Address> = std::iter::once(lender)
+                .chain(
+                    calls
+                        .iter()
+                        .filter(|c| {
+                            c.parent == frame.index && c.index < callback.index
+                        })
+                        .map(|c| c.to),
+                )
+                .collect();
+            let in_frame = |f: &&&MoneyFlow| f.call_path.contains(&frame.index);
+            for loan in flows.iter().filter(in_frame).filter(|f| {
+                f.to == borrower
+                    && reserves.contains(&f.from)
+                    && *f.call_path.last().unwrap_or(&0) < callback.index
+            }) {
+                // callbacks of nested calls to the lender see the same loan
+                if loans.contains(&loan.index) {
+                    continue;
+                }
+                let reserve = loan.from;
+                let repayments: Vec<&MoneyFlow> = flows
+                    .iter()
+                    .filter(in_frame)
+                    .filter(|f| {
+                        f.index > loan.index
+                            && (f.to == reserve || f.to == lender)
+                    })
+                    .copied()
+                    .collect();
+                let repaid = repayments
+                    .iter()
+                    .filter(|f| f.asset() == loan.asset())
+                    .fold(U256::ZERO, |sum, f| sum.saturating_add(f.amount));
+                let repayment = if repaid >= loan.amount {
+                    repaid.to_string()
+                } else if let Some(swap) = repayments.iter().find(|f| {
+                    f.asset() != loan.asset()
+                        && Self::uses_loan(
+                            calls,
+                            callback,
+                            &[Some(lender), Some(reserve), loan.token, f.token],
+                        )
+                }) {
+                    format!("{} {}", swap.amount, swap.asset())
+                } else {
+                    continue;
+                };
+                loans.push(loan.index);
+                findings.push(Finding {
+                    detector: self.name().to_string(),
+                    description: format!(
+                        "{} borrows {} {} from {} and repays {}",
+                        borrower,
+                        loan.amount,
+                        loan.asset(),
+                        lender,
+                        repayment
+                    ),
+                    calls: vec![frame.index, callback.index],
+                    addresses: vec![borrower, lender],
+                });
+            }
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{detectors::Detector, testing::fixtures};
+
+    use super::FlashLoanDetector;
+
+    #[test]
+    fn test_flash_loan() {
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract Lender {
+            function flashLoan(Token t, uint256 amount) public {
+                t.transfer(msg.sender, amount);
+                Borrower(msg.sender).onFlashLoan(t, amount);
+                t.transferFrom(msg.sender, address(this), amount + 1);
+            }
+        }
+        contract Borrower {
+            function borrow(Lender lender, Token t) public {
+                lender.flashLoan(t, 100);
+            }
+            function onFlashLoan(Token t, uint256 amount) public {}
+        }
+        contract Main {
+            function run() public {
+                Token t = new Token();
+                Lender lender = new Lender();
+                Borrower borrower = new Borrower();
+                // a transfer followed by calls to the receiver, but no repayment
+                t.transfer(address(borrower), 1);
+                borrower.onFlashLoan(t, 0);
+                borrower.borrow(lender, t);
+            }
+        }
+        "#,
+        );
+        let findings = FlashLoanDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0].description.contains("repays 101"));
+    }
+
+    #[test]
+    fn test_aave_style_flash_loan() {
+        // the pool sends the loan through an aToken and takes the repayment into it
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract AToken {
+            function transferUnderlyingTo(Token t, address to, uint256 amount) public {
+                t.transfer(to, amount);
+            }
+        }
+        contract Pool {
+            AToken public aToken = new AToken();
+            function flashLoan(Receiver receiver, Token t, uint256 amount) public {
+                aToken.transferUnderlyingTo(t, address(receiver), amount);
+                receiver.executeOperation(t, amount);
+                t.transferFrom(address(receiver), address(aToken), amount + 9);
+            }
+        }
+        contract Receiver {
+            function borrow(Pool pool, Token t) public {
+                pool.flashLoan(this, t, 1000);
+            }
+            function executeOperation(Token t, uint256 amount) public {}
+        }
+        contract Main {
+            function run() public {
+                Token t = new Token();
+                Pool pool = new Pool();
+                Receiver receiver = new Receiver();
+                receiver.borrow(pool, t);
+            }
+        }
+        "#,
+        );
+        let findings = FlashLoanDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0].description.contains("repays 1009"));
+    }
+
+    #[test]
+    fn test_flash_swap() {
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract Pair {
+            Token public token0;
+            Token public token1;
+            constructor(Token t0, Token t1) {
+                token0 = t0;
+                token1 = t1;
+            }
+            function swap(uint256 amount, Swapper to) public {
+                token0.transfer(address(to), amount);
+                to.swapCallback(token1, amount);
+            }
+        }
+        contract Market {
+            function trade() public {}
+        }
+        contract Swapper {
+            Market market;
+            constructor(Market m) {
+                market = m;
+            }
+            function swapCallback(Token t, uint256 amount) public {
+                if (address(market) != address(0)) {
+                    market.trade();
+                }
+                t.transfer(msg.sender, amount * 2);
+            }
+        }
+        contract Main {
+            function run() public {
+                Pair pair = new Pair(new Token(), new Token());
+                // a flash swap using the borrowed tokens, and a swap paid in the callback
+                pair.swap(100, new Swapper(new Market()));
+                pair.swap(100, new Swapper(Market(address(0))));
+            }
+        }
+        "#,
+        );
+        let findings = FlashLoanDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0].description.contains("repays 200 "));
+    }
+}
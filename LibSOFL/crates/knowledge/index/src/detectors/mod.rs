@@ -0,0 +1,121 @@
+//! Detectors of attack patterns in traced transactions.
+//!
+//! Detectors work on the money flows and the call tree of a `TxTrace`,
+//! so both must be traced (the default `TraceConfig`).
+
+use std::fmt;
+
+use libsofl_core::engine::types::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::trace::{CallTrace, MoneyFlow, TokenKind, TxTrace};
+
+pub mod flash_loan;
+pub mod price_manipulation;
+pub mod profit;
+pub mod reentrancy;
+
+pub use flash_loan::FlashLoanDetector;
+pub use price_manipulation::PriceManipulationDetector;
+pub use profit::FreshContractProfitDetector;
+pub use reentrancy::ReentrancyDetector;
+
+/// An attack pattern found in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Name of the detector.
+    pub detector: String,
+    pub description: String,
+    /// Indices of the call frames involved.
+    pub calls: Vec<usize>,
+    /// Addresses involved, the most relevant first.
+    pub addresses: Vec<Address>,
+}
+
+/// Detector of an attack pattern.
+pub trait Detector: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn detect(&self, trace: &TxTrace) -> Vec<Finding>;
+}
+
+/// A set of detectors run together.
+/// The default set contains all detectors in this module with default parameters.
+pub struct DetectorSet {
+    detectors: Vec<Box<dyn Detector>>,
+}
+
+impl Default for DetectorSet {
+    fn default() -> Self {
+        Self::empty()
+            .with(FlashLoanDetector)
+            .with(ReentrancyDetector)
+            .with(FreshContractProfitDetector::default())
+            .with(PriceManipulationDetector)
+    }
+}
+
+impl fmt::Debug for DetectorSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.detectors.iter().map(|d| d.name()))
+            .finish()
+    }
+}
+
+impl DetectorSet {
+    /// A set without any detector.
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, detector: impl Detector + 'static) -> &mut Self {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// Builder-style `register`.
+    pub fn with(mut self, detector: impl Detector + 'static) -> Self {
+        self.register(detector);
+        self
+    }
+
+    /// Run all detectors on the trace.
+    pub fn detect(&self, trace: &TxTrace) -> Vec<Finding> {
+        self.detectors
+            .iter()
+            .flat_map(|d| d.detect(trace))
+            .collect()
+    }
+}
+
+/// Whether the asset of the flow is ether or a fungible token.
+pub(crate) fn is_fungible(flow: &MoneyFlow) -> bool {
+    matches!(
+        flow.kind,
+        TokenKind::Eth | TokenKind::Erc20 | TokenKind::Erc777
+    )
+}
+
+/// Call frames from the parent of the call frame up to the outermost one.
+pub(crate) fn ancestors<'a>(
+    calls: &'a [CallTrace],
+    call: &'a CallTrace,
+) -> impl Iterator<Item = &'a CallTrace> + 'a {
+    std::iter::successors(Some(call), move |c| {
+        (c.parent > 0).then(|| &calls[c.parent - 1])
+    })
+    .skip(1)
+}
+
+/// Whether the call frame `index` is `root` or one of its descendants.
+pub(crate) fn in_subtree(
+    calls: &[CallTrace],
+    root: usize,
+    index: usize,
+) -> bool {
+    index == root
+        || ancestors(calls, &calls[index - 1]).any(|c| c.index == root)
+}
@@ -0,0 +1,140 @@
+use crate::trace::{Asset, CallTrace, TxTrace};
+
+use super::{ancestors, in_subtree, is_fungible, Detector, Finding};
+
+/// A call to a pool in which the pool receives one asset and sends out another.
+struct Swap<'a> {
+    call: &'a CallTrace,
+    sent: Asset,
+    received: Asset,
+}
+
+/// Detects pool manipulation around a swap: a contract swaps on a pool, another contract
+/// interacts with the pool (e.g., reads its reserves as a price oracle or swaps),
+/// and then the first contract swaps back on the pool.
+pub struct PriceManipulationDetector;
+
+impl PriceManipulationDetector {
+    fn swaps(trace: &TxTrace) -> Vec<Swap<'_>> {
+        let calls = &trace.calls;
+        let mut swaps = Vec::new();
+        for call in calls.iter().filter(|c| !c.reverted && !c.kind.is_create())
+        {
+            let pool = call.to;
+            if ancestors(calls, call).any(|a| a.to == pool) {
+                continue;
+            }
+            let flows = trace.money_flows.iter().filter(|f| {
+                !f.reverted
+                    && is_fungible(f)
+                    && f.call_path.contains(&call.index)
+            });
+            let (mut sent, mut received) = (None, None);
+            for flow in flows {
+                if flow.to == pool && sent.is_none() {
+                    sent = Some(flow.asset());
+                } else if flow.from == pool && received.is_none() {
+                    received = Some(flow.asset());
+                }
+            }
+            if let (Some(sent), Some(received)) = (sent, received) {
+                if sent != received {
+                    swaps.push(Swap {
+                        call,
+                        sent,
+                        received,
+                    });
+                }
+            }
+        }
+        swaps
+    }
+}
+
+impl Detector for PriceManipulationDetector {
+    fn name(&self) -> &'static str {
+        "price-manipulation"
+    }
+
+    fn detect(&self, trace: &TxTrace) -> Vec<Finding> {
+        let calls = &trace.calls;
+        let swaps = Self::swaps(trace);
+        let mut findings = Vec::new();
+        for (i, back) in swaps.iter().enumerate() {
+            let (pool, trader) = (back.call.to, back.call.from);
+            let manipulated = swaps[..i].iter().find_map(|front| {
+                if front.call.to != pool
+                    || front.call.from != trader
+                    || front.sent != back.received
+                    || front.received != back.sent
+                {
+                    return None;
+                }
+                calls[front.call.index..back.call.index - 1]
+                    .iter()
+                    .find(|c| {
+                        c.to == pool
+                            && c.from != trader
+                            && !in_subtree(calls, front.call.index, c.index)
+                    })
+                    .map(|middle| (front, middle))
+            });
+            let Some((front, middle)) = manipulated else {
+                continue;
+            };
+            findings.push(Finding {
+                detector: self.name().to_string(),
+                description: format!(
+                    "{} swaps {} for {} on {} before {} interacts with it, and swaps back",
+                    trader, front.sent, front.received, pool, middle.from
+                ),
+                calls: vec![front.call.index, middle.index, back.call.index],
+                addresses: vec![pool, trader, middle.from],
+            });
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{detectors::Detector, testing::fixtures};
+
+    use super::PriceManipulationDetector;
+
+    #[test]
+    fn test_price_manipulation() {
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract Pool {
+            uint256 public reserve;
+            function swap(Token tokenIn, Token tokenOut, uint256 amount) public {
+                tokenIn.transferFrom(msg.sender, address(this), amount);
+                tokenOut.transfer(msg.sender, amount);
+                reserve += amount;
+            }
+        }
+        contract Victim {
+            function borrow(Pool pool) public view returns (uint256) {
+                return pool.reserve();
+            }
+        }
+        contract Main {
+            function run() public {
+                Token a = new Token();
+                Token b = new Token();
+                Pool pool = new Pool();
+                Victim victim = new Victim();
+                pool.swap(a, b, 100);
+                pool.swap(a, b, 1);
+                victim.borrow(pool);
+                pool.swap(b, a, 101);
+            }
+        }
+        "#,
+        );
+        let findings = PriceManipulationDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert_eq!(findings[0].calls.len(), 3);
+    }
+}
@@ -0,0 +1,120 @@
+use libsofl_core::engine::types::{Address, U256};
+
+use crate::trace::{TokenKind, TxTrace};
+
+use super::{is_fungible, Detector, Finding};
+
+/// Detects a transaction creating contracts that make a large profit for the sending EOA.
+/// The sender and the contracts created in the transaction are seen as one party, whose
+/// profit is the value of the assets it receives from others minus the value it sends.
+/// Only ether and money flows annotated with values (see `valuation`) are counted.
+pub struct FreshContractProfitDetector {
+    /// Minimum profit in wei to report.
+    pub min_profit: U256,
+}
+
+impl Default for FreshContractProfitDetector {
+    fn default() -> Self {
+        Self {
+            min_profit: U256::from(10).pow(U256::from(19)),
+        }
+    }
+}
+
+impl Detector for FreshContractProfitDetector {
+    fn name(&self) -> &'static str {
+        "fresh-contract-profit"
+    }
+
+    fn detect(&self, trace: &TxTrace) -> Vec<Finding> {
+        let Some(sender) = trace.calls.first().map(|c| c.from) else {
+            return Vec::new();
+        };
+        let created: Vec<&_> = trace
+            .calls
+            .iter()
+            .filter(|c| c.kind.is_create() && !c.reverted)
+            .collect();
+        if created.is_empty() {
+            return Vec::new();
+        }
+        let mut party: Vec<Address> = vec![sender];
+        party.extend(created.iter().map(|c| c.to));
+
+        let (mut gained, mut lost) = (U256::ZERO, U256::ZERO);
+        for flow in trace
+            .money_flows
+            .iter()
+            .filter(|f| !f.reverted && is_fungible(f))
+        {
+            let Some(value) = flow
+                .value
+                .or((flow.kind == TokenKind::Eth).then_some(flow.amount))
+            else {
+                continue;
+            };
+            match (party.contains(&flow.from), party.contains(&flow.to)) {
+                (false, true) => gained = gained.saturating_add(value),
+                (true, false) => lost = lost.saturating_add(value),
+                _ => {}
+            }
+        }
+        if gained < lost || gained - lost < self.min_profit {
+            return Vec::new();
+        }
+        vec![Finding {
+            detector: self.name().to_string(),
+            description: format!(
+                "{} profits {} wei via {} contracts created in the transaction",
+                sender,
+                gained - lost,
+                created.len()
+            ),
+            calls: created.iter().map(|c| c.index).collect(),
+            addresses: party,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::engine::types::U256;
+
+    use crate::{detectors::Detector, testing::fixtures};
+
+    use super::FreshContractProfitDetector;
+
+    #[test]
+    fn test_fresh_contract_profit() {
+        let ether = U256::from(10).pow(U256::from(18));
+        let trace = fixtures::trace_with_token_funded(
+            r#"
+        contract Attack {
+            constructor(Main main) {
+                main.withdraw();
+                payable(tx.origin).transfer(address(this).balance);
+            }
+        }
+        contract Main {
+            constructor() payable {}
+            function withdraw() public {
+                payable(msg.sender).transfer(address(this).balance);
+            }
+            function run() public {
+                new Attack(this);
+            }
+        }
+        "#,
+            ether * U256::from(5),
+        );
+        let detector = FreshContractProfitDetector { min_profit: ether };
+        let findings = detector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0]
+            .description
+            .contains(&(ether * U256::from(5)).to_string()));
+
+        let detector = FreshContractProfitDetector::default();
+        assert!(detector.detect(&trace).is_empty());
+    }
+}
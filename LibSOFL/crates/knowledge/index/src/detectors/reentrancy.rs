@@ -0,0 +1,179 @@
+use crate::trace::{CallKind, TxTrace};
+
+use super::{ancestors, Detector, Finding};
+
+/// Detects reentrancy: a contract calls back the contract that entered it, and while its
+/// outermost call frame is still on the call stack, it is called again, either by that
+/// contract or by another one the callback reaches (cross-contract reentrancy).
+/// The callbacks themselves (e.g., flash loan callbacks), calls into the contract by those
+/// it calls without such a callback (e.g., a strategy reading its vault), direct self-calls
+/// and delegate calls are not reentrancy.
+/// Static calls are reported as read-only reentrancy.
+pub struct ReentrancyDetector;
+
+impl Detector for ReentrancyDetector {
+    fn name(&self) -> &'static str {
+        "reentrancy"
+    }
+
+    fn detect(&self, trace: &TxTrace) -> Vec<Finding> {
+        let calls = &trace.calls;
+        let mut entered = Vec::new();
+        let mut findings = Vec::new();
+        for call in calls.iter().filter(|c| {
+            !c.reverted
+                && !c.kind.is_create()
+                && c.kind != CallKind::SelfDestruct
+        }) {
+            let target = call.to;
+            let Some(parent) = ancestors(calls, call).next() else {
+                continue;
+            };
+            if parent.to == target {
+                continue;
+            }
+            let Some(outermost) =
+                ancestors(calls, call).filter(|a| a.to == target).last()
+            else {
+                continue;
+            };
+            // the target has called back the contract entering it
+            let entering = outermost.from;
+            let called_back = ancestors(calls, call)
+                .take_while(|a| a.index != outermost.index)
+                .any(|a| a.to == entering);
+            if !called_back || entered.contains(&outermost.index) {
+                continue;
+            }
+            entered.push(outermost.index);
+            let kind = if call.kind == CallKind::StaticCall {
+                "read-only reentrancy"
+            } else {
+                "reentrancy"
+            };
+            let mut description =
+                format!("{} into {} by {}", kind, target, call.from);
+            let mut addresses = vec![target, call.from];
+            if call.from != entering {
+                description += &format!(" during a callback to {}", entering);
+                addresses.push(entering);
+            }
+            findings.push(Finding {
+                detector: self.name().to_string(),
+                description,
+                calls: vec![outermost.index, call.index],
+                addresses,
+            });
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{detectors::Detector, testing::fixtures};
+
+    use super::ReentrancyDetector;
+
+    #[test]
+    fn test_reentrancy() {
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract Bank {
+            uint256 public count;
+            function withdraw() public {
+                count += 1;
+                Attacker(msg.sender).receiveMoney();
+            }
+            function self() public {
+                this.count();
+            }
+        }
+        contract Attacker {
+            bool entered;
+            function attack(Bank bank) public {
+                bank.withdraw();
+            }
+            function receiveMoney() public {
+                if (!entered) {
+                    entered = true;
+                    Bank(msg.sender).withdraw();
+                }
+            }
+        }
+        contract Main {
+            function run() public {
+                Bank bank = new Bank();
+                Attacker attacker = new Attacker();
+                bank.self();
+                attacker.attack(bank);
+            }
+        }
+        "#,
+        );
+        let findings = ReentrancyDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0].description.starts_with("reentrancy"));
+    }
+
+    #[test]
+    fn test_cross_contract_reentrancy() {
+        let trace = fixtures::trace_with_token(
+            r#"
+        contract Pool {
+            uint256 public price = 1;
+            function removeLiquidity() public {
+                price = 0;
+                Attacker(msg.sender).receiveEther();
+                price = 1;
+            }
+        }
+        contract Lending {
+            function borrow(Pool pool) public view returns (uint256) {
+                return pool.price();
+            }
+        }
+        contract Attacker {
+            Pool pool;
+            Lending lending;
+            constructor(Pool p, Lending l) {
+                pool = p;
+                lending = l;
+            }
+            function attack() public {
+                pool.removeLiquidity();
+            }
+            function receiveEther() public {
+                lending.borrow(pool);
+            }
+        }
+        contract Vault {
+            uint256 public total;
+            function report(Strategy strategy) public {
+                strategy.harvest(this);
+            }
+        }
+        contract Strategy {
+            function harvest(Vault vault) public view returns (uint256) {
+                return vault.total();
+            }
+        }
+        contract Main {
+            function run() public {
+                // a contract reading the one calling it is not reentrancy
+                Vault vault = new Vault();
+                vault.report(new Strategy());
+                Pool pool = new Pool();
+                Attacker attacker = new Attacker(pool, new Lending());
+                attacker.attack();
+            }
+        }
+        "#,
+        );
+        let findings = ReentrancyDetector.detect(&trace);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert!(findings[0].description.starts_with("read-only reentrancy"));
+        assert!(findings[0].description.contains("during a callback to"));
+        assert_eq!(findings[0].addresses.len(), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use libsofl_core::engine::{
+    state::BcState,
+    types::{Address, EVMData, B256, U256},
+};
+use libsofl_utils::log::debug;
+
+use crate::trace::{FlowAction, TokenKind};
+
+pub mod registry;
+pub mod standard;
+
+pub use registry::{
+    EmitterDecoder, EventDecoder, EventLog, EventRegistry, SolEventDecoder,
+};
+
+/// A token transfer carried by an event log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenTransfer {
+    /// Name of the event carrying the transfer, e.g., `Transfer` or `Deposit`.
+    pub event: String,
+    pub from: Address,
+    pub to: Address,
+    pub kind: TokenKind,
+    pub action: FlowAction,
+    pub amount: U256,
+    pub token_id: Option<U256>,
+}
+
+impl TokenTransfer {
+    /// Create a token transfer. Transfers from the zero address are mints
+    /// and transfers to the zero address are burns.
+    pub fn new(
+        event: impl Into<String>,
+        kind: TokenKind,
+        from: Address,
+        to: Address,
+        amount: U256,
+        token_id: Option<U256>,
+    ) -> Self {
+        let action = if from.is_zero() {
+            FlowAction::Mint
+        } else if to.is_zero() {
+            FlowAction::Burn
+        } else {
+            FlowAction::Transfer
+        };
+        Self {
+            event: event.into(),
+            from,
+            to,
+            kind,
+            action,
+            amount,
+            token_id,
+        }
+    }
+}
+
+/// Decode the token transfers carried by a log emitted during execution.
+/// Logs that are known to the registry but cannot be decoded are skipped.
+pub fn transfers_in_log<BS: BcState>(
+    registry: &EventRegistry,
+    evm_data: &EVMData<'_, BS>,
+    address: &Address,
+    topics: &[B256],
+    data: &[u8],
+) -> Vec<TokenTransfer> {
+    let code = evm_data
+        .journaled_state
+        .state
+        .get(address)
+        .and_then(|account| account.info.code.as_ref())
+        .map(|code| code.bytes().as_ref())
+        .unwrap_or_default();
+    let log = EventLog {
+        address: *address,
+        topics,
+        data,
+        code,
+    };
+    registry.decode(&log).unwrap_or_else(|e| {
+        debug!(token = %address, err = %e, "skip malformed token event");
+        Vec::new()
+    })
+}
@@ -0,0 +1,252 @@
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc};
+
+use alloy_sol_types::SolEvent;
+use libsofl_core::{
+    engine::types::{Address, B256},
+    error::SoflError,
+};
+
+use super::{standard, TokenTransfer};
+
+/// A log emitted during execution.
+#[derive(Clone, Copy, Debug)]
+pub struct EventLog<'a> {
+    /// The contract emitting the log.
+    pub address: Address,
+    pub topics: &'a [B256],
+    pub data: &'a [u8],
+    /// Bytecode of the emitting contract, empty if unknown.
+    pub code: &'a [u8],
+}
+
+/// Decoder of the token transfers carried by one kind of event.
+pub trait EventDecoder: Send + Sync {
+    /// topic0 of the event.
+    fn topic0(&self) -> B256;
+
+    /// Number of topics of the event, including topic0.
+    fn topic_count(&self) -> usize;
+
+    /// Decode the token transfers in the log.
+    /// The log is guaranteed to match `topic0` and `topic_count`.
+    fn decode(
+        &self,
+        log: &EventLog<'_>,
+    ) -> Result<Vec<TokenTransfer>, SoflError>;
+}
+
+/// An `EventDecoder` built from a `sol!` event and a function converting the decoded event.
+pub struct SolEventDecoder<E, F> {
+    topic_count: usize,
+    convert: F,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E, F> SolEventDecoder<E, F>
+where
+    E: SolEvent,
+    F: Fn(E) -> Result<Vec<TokenTransfer>, SoflError> + Send + Sync,
+{
+    pub fn new(topic_count: usize, convert: F) -> Self {
+        Self {
+            topic_count,
+            convert,
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<E, F> EventDecoder for SolEventDecoder<E, F>
+where
+    E: SolEvent,
+    F: Fn(E) -> Result<Vec<TokenTransfer>, SoflError> + Send + Sync,
+{
+    fn topic0(&self) -> B256 {
+        E::SIGNATURE_HASH
+    }
+
+    fn topic_count(&self) -> usize {
+        self.topic_count
+    }
+
+    fn decode(
+        &self,
+        log: &EventLog<'_>,
+    ) -> Result<Vec<TokenTransfer>, SoflError> {
+        let event = E::decode_log(log.topics.iter().copied(), log.data, true)
+            .map_err(|e| {
+            SoflError::Abi(format!("failed to decode {}: {}", E::SIGNATURE, e))
+        })?;
+        (self.convert)(event)
+    }
+}
+
+/// An `EventDecoder` only decoding the logs emitted by one contract,
+/// for events whose signature is shared by unrelated contracts.
+/// Logs emitted by other contracts carry no transfer.
+pub struct EmitterDecoder<D> {
+    emitter: Address,
+    inner: D,
+}
+
+impl<D: EventDecoder> EmitterDecoder<D> {
+    pub fn new(emitter: Address, inner: D) -> Self {
+        Self { emitter, inner }
+    }
+}
+
+impl<D: EventDecoder> EventDecoder for EmitterDecoder<D> {
+    fn topic0(&self) -> B256 {
+        self.inner.topic0()
+    }
+
+    fn topic_count(&self) -> usize {
+        self.inner.topic_count()
+    }
+
+    fn decode(
+        &self,
+        log: &EventLog<'_>,
+    ) -> Result<Vec<TokenTransfer>, SoflError> {
+        if log.address != self.emitter {
+            return Ok(Vec::new());
+        }
+        self.inner.decode(log)
+    }
+}
+
+/// Registry of event decoders, keyed by topic0 and topic count.
+/// The default registry contains the decoders of standard token events (see `standard`).
+#[derive(Clone)]
+pub struct EventRegistry {
+    decoders: HashMap<(B256, usize), Arc<dyn EventDecoder>>,
+}
+
+impl Default for EventRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        standard::register_all(&mut registry);
+        registry
+    }
+}
+
+impl fmt::Debug for EventRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.decoders.keys()).finish()
+    }
+}
+
+impl EventRegistry {
+    /// A registry without any decoder.
+    pub fn empty() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Register a decoder, replacing the one with the same topic0 and topic count.
+    pub fn register(
+        &mut self,
+        decoder: impl EventDecoder + 'static,
+    ) -> &mut Self {
+        let key = (decoder.topic0(), decoder.topic_count());
+        self.decoders.insert(key, Arc::new(decoder));
+        self
+    }
+
+    /// Builder-style `register`.
+    pub fn with(mut self, decoder: impl EventDecoder + 'static) -> Self {
+        self.register(decoder);
+        self
+    }
+
+    pub fn contains(&self, topic0: B256, topic_count: usize) -> bool {
+        self.decoders.contains_key(&(topic0, topic_count))
+    }
+
+    /// Decode the token transfers in the log.
+    /// Returns an empty vector if no decoder is registered for the log's topic0,
+    /// or an error if the log cannot be decoded by the decoders registered for its topic0.
+    pub fn decode(
+        &self,
+        log: &EventLog<'_>,
+    ) -> Result<Vec<TokenTransfer>, SoflError> {
+        let Some(topic0) = log.topics.first().copied() else {
+            return Ok(Vec::new());
+        };
+        match self.decoders.get(&(topic0, log.topics.len())) {
+            Some(decoder) => decoder.decode(log),
+            None if self.decoders.keys().any(|(t, _)| *t == topic0) => {
+                Err(SoflError::Abi(format!(
+                    "event {} with unexpected {} topics",
+                    topic0,
+                    log.topics.len()
+                )))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_sol_macro::sol;
+    use alloy_sol_types::SolEvent;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{Address, U256},
+    };
+
+    use crate::{
+        events::{EventLog, EventRegistry, SolEventDecoder, TokenTransfer},
+        trace::{FlowAction, TokenKind},
+    };
+
+    sol! {
+        event Swap(address indexed sender, address indexed recipient, uint256 amountIn);
+    }
+
+    #[test]
+    fn test_custom_decoder() {
+        let registry =
+            EventRegistry::empty().with(SolEventDecoder::new(3, |ev: Swap| {
+                Ok(vec![TokenTransfer::new(
+                    "Swap",
+                    TokenKind::Erc20,
+                    ev.sender,
+                    ev.recipient,
+                    ev.amountIn,
+                    None,
+                )])
+            }));
+        let sender: Address = 0x1.cvt();
+        let recipient: Address = 0x2.cvt();
+        let topics = [
+            Swap::SIGNATURE_HASH,
+            sender.into_word(),
+            recipient.into_word(),
+        ];
+        let data = U256::from(42).to_be_bytes::<32>();
+        let log = EventLog {
+            address: Address::ZERO,
+            topics: &topics,
+            data: &data,
+            code: &[],
+        };
+        let transfers = registry.decode(&log).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].event, "Swap");
+        assert_eq!(transfers[0].action, FlowAction::Transfer);
+        assert_eq!(transfers[0].amount, U256::from(42));
+
+        // wrong topic count of a known event
+        let log = EventLog {
+            topics: &topics[..2],
+            ..log
+        };
+        assert!(registry.decode(&log).is_err());
+
+        // unknown events are ignored
+        assert!(EventRegistry::empty().decode(&log).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,452 @@
+//! Decoders of standard token events, using the ABIs in `libsofl-periphery`.
+//!
+//! Each token movement is decoded from exactly one event, so that summing up the
+//! transfers never counts a movement twice. ERC777 tokens emit `Transfer` alongside
+//! `Sent`, `Minted` and `Burned` as well, so their decoders are only registered by
+//! `register_erc777`, for tokens that do not.
+
+use alloy_sol_types::SolEvent;
+use libsofl_core::{
+    engine::types::{opcode, Address, B256, U256},
+    error::SoflError,
+};
+use libsofl_periphery::{
+    addressbook::{
+        WethABI, ADDRESS_BOOK, ERC1155ABI, ERC20ABI, ERC721ABI, ERC777ABI,
+    },
+    types::Chain,
+};
+
+use crate::trace::TokenKind;
+
+use super::{
+    EmitterDecoder, EventDecoder, EventLog, EventRegistry, SolEventDecoder,
+    TokenTransfer,
+};
+
+/// `decimals()`
+const DECIMALS_SELECTOR: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+/// `ownerOf(uint256)`
+const OWNER_OF_SELECTOR: [u8; 4] = [0x63, 0x52, 0x21, 0x1e];
+
+/// Register the decoders of all standard token events except ERC777 ones (see `register_erc777`).
+pub fn register_all(registry: &mut EventRegistry) {
+    // erc20, also emitted by erc777 tokens and erc4626 vaults
+    registry.register(SolEventDecoder::new(3, |ev: ERC20ABI::Transfer| {
+        Ok(vec![TokenTransfer::new(
+            "Transfer",
+            TokenKind::Erc20,
+            ev.from,
+            ev.to,
+            ev.value,
+            None,
+        )])
+    }));
+    registry.register(Erc721TransferDecoder);
+
+    // erc1155
+    registry.register(SolEventDecoder::new(
+        4,
+        |ev: ERC1155ABI::TransferSingle| {
+            Ok(vec![TokenTransfer::new(
+                "TransferSingle",
+                TokenKind::Erc1155,
+                ev.from,
+                ev.to,
+                ev.value,
+                Some(ev.id),
+            )])
+        },
+    ));
+    registry.register(SolEventDecoder::new(
+        4,
+        |ev: ERC1155ABI::TransferBatch| {
+            if ev.ids.len() != ev.values.len() {
+                return Err(SoflError::Abi(format!(
+                    "TransferBatch has {} ids but {} values",
+                    ev.ids.len(),
+                    ev.values.len()
+                )));
+            }
+            Ok(ev
+                .ids
+                .into_iter()
+                .zip(ev.values)
+                .map(|(id, value)| {
+                    TokenTransfer::new(
+                        "TransferBatch",
+                        TokenKind::Erc1155,
+                        ev.from,
+                        ev.to,
+                        value,
+                        Some(id),
+                    )
+                })
+                .collect())
+        },
+    ));
+
+    // weth on mainnet, use `register_weth` for other chains
+    register_weth(registry, ADDRESS_BOOK.weth.must_on_chain(Chain::Mainnet));
+}
+
+/// Register the decoders of `Deposit` and `Withdrawal` of the WETH contract at the given address,
+/// i.e., wrapping mints and unwrapping burns WETH.
+/// Logs with the same signatures emitted by other contracts are not decoded.
+pub fn register_weth(registry: &mut EventRegistry, weth: Address) {
+    registry.register(EmitterDecoder::new(
+        weth,
+        SolEventDecoder::new(2, |ev: WethABI::Deposit| {
+            Ok(vec![TokenTransfer::new(
+                "Deposit",
+                TokenKind::Erc20,
+                Address::ZERO,
+                ev.dst,
+                ev.wad,
+                None,
+            )])
+        }),
+    ));
+    registry.register(EmitterDecoder::new(
+        weth,
+        SolEventDecoder::new(2, |ev: WethABI::Withdrawal| {
+            Ok(vec![TokenTransfer::new(
+                "Withdrawal",
+                TokenKind::Erc20,
+                ev.src,
+                Address::ZERO,
+                ev.wad,
+                None,
+            )])
+        }),
+    ));
+}
+
+/// Register the decoders of `Sent`, `Minted` and `Burned` of ERC777 tokens.
+/// Only use it for tokens that do not emit ERC20 `Transfer` as well,
+/// otherwise every movement is decoded twice.
+pub fn register_erc777(registry: &mut EventRegistry) {
+    registry.register(SolEventDecoder::new(4, |ev: ERC777ABI::Sent| {
+        Ok(vec![TokenTransfer::new(
+            "Sent",
+            TokenKind::Erc777,
+            ev.from,
+            ev.to,
+            ev.amount,
+            None,
+        )])
+    }));
+    registry.register(SolEventDecoder::new(3, |ev: ERC777ABI::Minted| {
+        Ok(vec![TokenTransfer::new(
+            "Minted",
+            TokenKind::Erc777,
+            Address::ZERO,
+            ev.to,
+            ev.amount,
+            None,
+        )])
+    }));
+    registry.register(SolEventDecoder::new(3, |ev: ERC777ABI::Burned| {
+        Ok(vec![TokenTransfer::new(
+            "Burned",
+            TokenKind::Erc777,
+            ev.from,
+            Address::ZERO,
+            ev.amount,
+            None,
+        )])
+    }));
+}
+
+/// Decoder of `Transfer` with 4 topics, which is an ERC721 transfer
+/// unless the emitting contract looks like an ERC20 token that indexes the amount.
+pub struct Erc721TransferDecoder;
+
+impl EventDecoder for Erc721TransferDecoder {
+    fn topic0(&self) -> B256 {
+        ERC721ABI::Transfer::SIGNATURE_HASH
+    }
+
+    fn topic_count(&self) -> usize {
+        4
+    }
+
+    fn decode(
+        &self,
+        log: &EventLog<'_>,
+    ) -> Result<Vec<TokenTransfer>, SoflError> {
+        // the token id (or amount) is indexed, so data is ignored
+        let ev = ERC721ABI::Transfer::decode_log(
+            log.topics.iter().copied(),
+            &[],
+            true,
+        )
+        .map_err(|e| {
+            SoflError::Abi(format!("failed to decode erc721 Transfer: {}", e))
+        })?;
+        let transfer = if is_indexed_erc20(log.code) {
+            TokenTransfer::new(
+                "Transfer",
+                TokenKind::Erc20,
+                ev.from,
+                ev.to,
+                ev.tokenId,
+                None,
+            )
+        } else {
+            TokenTransfer::new(
+                "Transfer",
+                TokenKind::Erc721,
+                ev.from,
+                ev.to,
+                U256::from(1),
+                Some(ev.tokenId),
+            )
+        };
+        Ok(vec![transfer])
+    }
+}
+
+/// Whether the contract looks like an ERC20 token rather than an ERC721 token,
+/// i.e., its dispatcher has `decimals()` but not `ownerOf(uint256)`.
+/// Proxies have neither, so they are treated as ERC721 by default.
+fn is_indexed_erc20(code: &[u8]) -> bool {
+    has_selector(code, DECIMALS_SELECTOR)
+        && !has_selector(code, OWNER_OF_SELECTOR)
+}
+
+fn has_selector(code: &[u8], selector: [u8; 4]) -> bool {
+    code.windows(5)
+        .any(|w| w[0] == opcode::PUSH4 && w[1..] == selector)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_sol_types::SolEvent;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{Address, B256, U256},
+    };
+    use libsofl_periphery::{
+        addressbook::{
+            WethABI, ADDRESS_BOOK, ERC1155ABI, ERC20ABI, ERC4626ABI, ERC777ABI,
+        },
+        types::Chain,
+    };
+
+    use crate::{
+        events::{EventLog, EventRegistry, TokenTransfer},
+        testing::fixtures,
+        trace::{FlowAction, TokenKind},
+    };
+
+    use super::register_erc777;
+
+    fn encode_words(words: &[U256]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|w| w.to_be_bytes::<32>().to_vec())
+            .collect()
+    }
+
+    fn decode(
+        topics: &[B256],
+        data: &[u8],
+        code: &[u8],
+    ) -> Result<Vec<TokenTransfer>, libsofl_core::error::SoflError> {
+        EventRegistry::default().decode(&EventLog {
+            address: Address::ZERO,
+            topics,
+            data,
+            code,
+        })
+    }
+
+    #[test]
+    fn test_erc20_transfer_amount_beyond_i128() {
+        let from: Address = 0x1.cvt();
+        let to: Address = 0x2.cvt();
+        let amount = U256::MAX - U256::from(1);
+        let topics = [
+            ERC20ABI::Transfer::SIGNATURE_HASH,
+            from.into_word(),
+            to.into_word(),
+        ];
+        let transfers = decode(&topics, &encode_words(&[amount]), &[]).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].kind, TokenKind::Erc20);
+        assert_eq!(transfers[0].action, FlowAction::Transfer);
+        assert_eq!(transfers[0].from, from);
+        assert_eq!(transfers[0].to, to);
+        assert_eq!(transfers[0].amount, amount);
+    }
+
+    #[test]
+    fn test_erc20_transfer_with_short_data() {
+        let topics =
+            [ERC20ABI::Transfer::SIGNATURE_HASH, B256::ZERO, B256::ZERO];
+        assert!(decode(&topics, &[0u8; 16], &[]).is_err());
+        assert!(decode(&topics[..2], &[0u8; 32], &[]).is_err());
+    }
+
+    #[test]
+    fn test_transfer_with_indexed_amount() {
+        let from: Address = 0x1.cvt();
+        let to: Address = 0x2.cvt();
+        let value = U256::from(1000);
+        let topics = [
+            ERC20ABI::Transfer::SIGNATURE_HASH,
+            from.into_word(),
+            to.into_word(),
+            B256::from(value.to_be_bytes::<32>()),
+        ];
+
+        // erc721 by default
+        let transfers = decode(&topics, &[], &[]).unwrap();
+        assert_eq!(transfers[0].kind, TokenKind::Erc721);
+        assert_eq!(transfers[0].amount, U256::from(1));
+        assert_eq!(transfers[0].token_id, Some(value));
+
+        // erc20 if the contract has `decimals()` but not `ownerOf(uint256)`
+        let code = [0x63, 0x31, 0x3c, 0xe5, 0x67, 0x14];
+        let transfers = decode(&topics, &[], &code).unwrap();
+        assert_eq!(transfers[0].kind, TokenKind::Erc20);
+        assert_eq!(transfers[0].from, from);
+        assert_eq!(transfers[0].to, to);
+        assert_eq!(transfers[0].amount, value);
+        assert_eq!(transfers[0].token_id, None);
+    }
+
+    #[test]
+    fn test_erc1155_transfer_batch() {
+        let from: Address = 0x1.cvt();
+        let to: Address = 0x2.cvt();
+        let large_id = U256::MAX;
+        let data = encode_words(&[
+            U256::from(0x40),
+            U256::from(0xa0),
+            U256::from(2),
+            U256::from(7),
+            large_id,
+            U256::from(2),
+            U256::from(100),
+            U256::from(200),
+        ]);
+        let topics = [
+            ERC1155ABI::TransferBatch::SIGNATURE_HASH,
+            B256::ZERO,
+            from.into_word(),
+            to.into_word(),
+        ];
+        let transfers = decode(&topics, &data, &[]).unwrap();
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].token_id, Some(U256::from(7)));
+        assert_eq!(transfers[0].amount, U256::from(100));
+        assert_eq!(transfers[1].token_id, Some(large_id));
+        assert_eq!(transfers[1].amount, U256::from(200));
+        assert!(transfers.iter().all(|t| t.kind == TokenKind::Erc1155));
+
+        // truncated arrays
+        assert!(decode(&topics, &data[..200], &[]).is_err());
+    }
+
+    #[test]
+    fn test_weth_deposit_and_withdrawal() {
+        let weth = ADDRESS_BOOK.weth.must_on_chain(Chain::Mainnet);
+        let user: Address = 0x1.cvt();
+        let wad = encode_words(&[U256::from(10)]);
+        let decode_from = |address: Address, topics: &[B256]| {
+            EventRegistry::default()
+                .decode(&EventLog {
+                    address,
+                    topics,
+                    data: &wad,
+                    code: &[],
+                })
+                .unwrap()
+        };
+
+        let topics = [WethABI::Deposit::SIGNATURE_HASH, user.into_word()];
+        let transfers = decode_from(weth, &topics);
+        assert_eq!(transfers[0].event, "Deposit");
+        assert_eq!(transfers[0].action, FlowAction::Mint);
+        assert_eq!(transfers[0].to, user);
+        assert_eq!(transfers[0].amount, U256::from(10));
+        // the same signature emitted by another contract is not a weth mint
+        assert!(decode_from(user, &topics).is_empty());
+
+        let topics = [WethABI::Withdrawal::SIGNATURE_HASH, user.into_word()];
+        let transfers = decode_from(weth, &topics);
+        assert!(decode_from(user, &topics).is_empty());
+        assert_eq!(transfers[0].event, "Withdrawal");
+        assert_eq!(transfers[0].action, FlowAction::Burn);
+        assert_eq!(transfers[0].from, user);
+    }
+
+    #[test]
+    fn test_erc4626_deposit_counted_once() {
+        // the share mint is decoded from the vault's `Transfer`, not from `Deposit`
+        let sender: Address = 0x1.cvt();
+        let owner: Address = 0x2.cvt();
+        let topics = [
+            ERC4626ABI::Deposit::SIGNATURE_HASH,
+            sender.into_word(),
+            owner.into_word(),
+        ];
+        let data = encode_words(&[U256::from(100), U256::from(90)]);
+        assert!(decode(&topics, &data, &[]).unwrap().is_empty());
+
+        let deposit = fixtures::vault_deposit();
+        let flows = &deposit.trace.money_flows;
+        let summary: Vec<_> = flows
+            .iter()
+            .map(|f| (f.event.as_deref(), f.action, f.amount))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("Transfer"), FlowAction::Mint, U256::from(100)),
+                (Some("Transfer"), FlowAction::Transfer, U256::from(100)),
+                (Some("Transfer"), FlowAction::Mint, U256::from(50)),
+            ]
+        );
+        assert_eq!(flows[1].from, deposit.main);
+        assert_eq!(flows[2].to, deposit.main);
+        assert_ne!(flows[1].token, flows[2].token);
+    }
+
+    #[test]
+    fn test_erc777_opt_in() {
+        let from: Address = 0x1.cvt();
+        let to: Address = 0x2.cvt();
+        let topics = [
+            ERC777ABI::Sent::SIGNATURE_HASH,
+            B256::ZERO,
+            from.into_word(),
+            to.into_word(),
+        ];
+        let data = encode_words(&[
+            U256::from(10),
+            U256::from(0x60),
+            U256::from(0x80),
+            U256::ZERO,
+            U256::ZERO,
+        ]);
+        assert!(decode(&topics, &data, &[]).unwrap().is_empty());
+
+        let mut registry = EventRegistry::empty();
+        register_erc777(&mut registry);
+        let transfers = registry
+            .decode(&EventLog {
+                address: Address::ZERO,
+                topics: &topics,
+                data: &data,
+                code: &[],
+            })
+            .unwrap();
+        assert_eq!(transfers[0].kind, TokenKind::Erc777);
+        assert_eq!(transfers[0].from, from);
+        assert_eq!(transfers[0].to, to);
+        assert_eq!(transfers[0].amount, U256::from(10));
+    }
+}
@@ -0,0 +1,116 @@
+use alloy_primitives::{hex, Selector};
+use libsofl_core::engine::types::{Address, U256};
+
+use crate::trace::{CallKind, CallTrace};
+
+use super::{Attributes, Graph};
+
+/// Call tree with a node per call frame and an edge from each call frame to its sub-calls.
+pub type CallGraph = Graph<CallNode, ()>;
+
+/// A call frame without its input, output and opcodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallNode {
+    pub index: usize,
+    pub kind: CallKind,
+    pub from: Address,
+    pub to: Address,
+    /// Function selector of message calls with at least 4 bytes of calldata.
+    pub selector: Option<Selector>,
+    /// Signature of the decoded function.
+    pub function: Option<String>,
+    pub value: U256,
+    pub success: bool,
+    pub gas_used: u64,
+    pub reverted: bool,
+}
+
+impl From<&CallTrace> for CallNode {
+    fn from(call: &CallTrace) -> Self {
+        let selector = match call.kind {
+            CallKind::Create | CallKind::Create2 | CallKind::SelfDestruct => {
+                None
+            }
+            _ => call.input.get(..4).map(Selector::from_slice),
+        };
+        Self {
+            index: call.index,
+            kind: call.kind,
+            from: call.from,
+            to: call.to,
+            selector,
+            function: call.function().map(str::to_string),
+            value: call.value,
+            success: call.is_success(),
+            gas_used: call.gas_used,
+            reverted: call.reverted,
+        }
+    }
+}
+
+impl CallGraph {
+    /// Build the call tree from the call frames of a transaction, in the order of `TxTrace::calls`.
+    /// The node of each call frame is at position `index - 1`.
+    pub fn from_calls(calls: &[CallTrace]) -> Self {
+        let mut graph = Self::new();
+        for call in calls {
+            let node = graph.add_node(CallNode::from(call));
+            debug_assert_eq!(node + 1, call.index, "bug: unordered calls");
+            if call.parent > 0 {
+                graph.add_edge(call.parent - 1, node, ());
+            }
+        }
+        graph
+    }
+
+    /// Positions of the outermost call frames.
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&n| self.incoming(n).next().is_none())
+    }
+}
+
+impl Attributes for CallNode {
+    fn keys() -> &'static [&'static str] {
+        &[
+            "index", "kind", "from", "to", "selector", "function", "value",
+            "success", "gas_used", "reverted",
+        ]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.index.to_string(),
+            self.kind.name().to_string(),
+            self.from.to_string(),
+            self.to.to_string(),
+            self.selector.map(hex::encode_prefixed).unwrap_or_default(),
+            self.function.clone().unwrap_or_default(),
+            self.value.to_string(),
+            self.success.to_string(),
+            self.gas_used.to_string(),
+            self.reverted.to_string(),
+        ]
+    }
+
+    fn label(&self) -> String {
+        match (&self.function, self.selector) {
+            (Some(function), _) => format!(
+                "{} {} {} {}",
+                self.index,
+                self.kind.name(),
+                self.to,
+                function
+            ),
+            (None, Some(selector)) => format!(
+                "{} {} {} {}",
+                self.index,
+                self.kind.name(),
+                self.to,
+                hex::encode_prefixed(selector)
+            ),
+            (None, None) => {
+                format!("{} {} {}", self.index, self.kind.name(), self.to)
+            }
+        }
+    }
+}
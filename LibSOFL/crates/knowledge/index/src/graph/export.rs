@@ -0,0 +1,336 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use libsofl_core::error::SoflError;
+
+use crate::dataset::io_err;
+
+use super::{Attributes, Graph};
+
+/// File format of exported graphs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    #[default]
+    GraphMl,
+    /// Graphviz DOT.
+    Dot,
+    /// A node list and an edge list in CSV.
+    Csv,
+}
+
+impl FromStr for GraphFormat {
+    type Err = SoflError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "dot" => Ok(GraphFormat::Dot),
+            "csv" => Ok(GraphFormat::Csv),
+            _ => Err(SoflError::Unsupported(format!("graph format {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Dot => "dot",
+            GraphFormat::Csv => "csv",
+        };
+        f.write_str(name)
+    }
+}
+
+impl<N: Attributes, E: Attributes> Graph<N, E> {
+    /// Save the graph into `<dir>/<name>.graphml`, `<dir>/<name>.dot`,
+    /// or `<dir>/<name>.nodes.csv` and `<dir>/<name>.edges.csv`.
+    pub fn save(
+        &self,
+        dir: impl AsRef<Path>,
+        name: &str,
+        format: GraphFormat,
+    ) -> Result<(), SoflError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| io_err(dir, e))?;
+        let create = |file: String| {
+            let path = dir.join(file);
+            File::create(&path)
+                .map(BufWriter::new)
+                .map_err(|e| io_err(&path, e))
+        };
+        let r = match format {
+            GraphFormat::GraphMl => {
+                let mut w = create(format!("{}.graphml", name))?;
+                self.write_graphml(&mut w).and_then(|_| w.flush())
+            }
+            GraphFormat::Dot => {
+                let mut w = create(format!("{}.dot", name))?;
+                self.write_dot(&mut w).and_then(|_| w.flush())
+            }
+            GraphFormat::Csv => {
+                let mut nodes = create(format!("{}.nodes.csv", name))?;
+                let mut edges = create(format!("{}.edges.csv", name))?;
+                self.write_csv(&mut nodes, &mut edges)
+                    .and_then(|_| nodes.flush())
+                    .and_then(|_| edges.flush())
+            }
+        };
+        r.map_err(|e| io_err(dir, e))
+    }
+
+    /// Write the graph in GraphML, with all attributes typed as strings.
+    pub fn write_graphml(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for key in N::keys() {
+            writeln!(
+                w,
+                r#"  <key id="n_{0}" for="node" attr.name="{0}" attr.type="string"/>"#,
+                key
+            )?;
+        }
+        for key in E::keys() {
+            writeln!(
+                w,
+                r#"  <key id="e_{0}" for="edge" attr.name="{0}" attr.type="string"/>"#,
+                key
+            )?;
+        }
+        writeln!(w, r#"  <graph id="G" edgedefault="directed">"#)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(w, r#"    <node id="n{}">"#, i)?;
+            write_graphml_data(w, "n", N::keys(), node.values())?;
+            writeln!(w, "    </node>")?;
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                w,
+                r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+                i, edge.source, edge.target
+            )?;
+            write_graphml_data(w, "e", E::keys(), edge.data.values())?;
+            writeln!(w, "    </edge>")?;
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")
+    }
+
+    /// Write the graph in Graphviz DOT, with attributes besides the labels.
+    pub fn write_dot(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "digraph G {{")?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                w,
+                "  n{} [{}];",
+                i,
+                dot_attributes(node.label(), N::keys(), node.values())
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                w,
+                "  n{} -> n{} [{}];",
+                edge.source,
+                edge.target,
+                dot_attributes(
+                    edge.data.label(),
+                    E::keys(),
+                    edge.data.values()
+                )
+            )?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Write the nodes as `id,<node attributes>`
+    /// and the edges as `source,target,<edge attributes>`, both with headers.
+    pub fn write_csv(
+        &self,
+        nodes: &mut impl Write,
+        edges: &mut impl Write,
+    ) -> io::Result<()> {
+        writeln!(
+            nodes,
+            "{}",
+            csv_row(["id"].into_iter().chain(N::keys().iter().copied()))
+        )?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let id = i.to_string();
+            let values = node.values();
+            writeln!(
+                nodes,
+                "{}",
+                csv_row(
+                    [id.as_str()]
+                        .into_iter()
+                        .chain(values.iter().map(String::as_str))
+                )
+            )?;
+        }
+        writeln!(
+            edges,
+            "{}",
+            csv_row(
+                ["source", "target"]
+                    .into_iter()
+                    .chain(E::keys().iter().copied())
+            )
+        )?;
+        for edge in &self.edges {
+            let (source, target) =
+                (edge.source.to_string(), edge.target.to_string());
+            let values = edge.data.values();
+            writeln!(
+                edges,
+                "{}",
+                csv_row(
+                    [source.as_str(), target.as_str()]
+                        .into_iter()
+                        .chain(values.iter().map(String::as_str))
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_graphml_data(
+    w: &mut impl Write,
+    prefix: &str,
+    keys: &[&str],
+    values: Vec<String>,
+) -> io::Result<()> {
+    for (key, value) in keys.iter().zip(values) {
+        if !value.is_empty() {
+            writeln!(
+                w,
+                r#"      <data key="{}_{}">{}</data>"#,
+                prefix,
+                key,
+                xml_escape(&value)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn dot_attributes(label: String, keys: &[&str], values: Vec<String>) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+    std::iter::once(format!("label={}", quote(&label)))
+        .chain(
+            keys.iter()
+                .zip(values)
+                .filter(|(_, v)| !v.is_empty())
+                .map(|(k, v)| format!("{}={}", quote(k), quote(&v))),
+        )
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn csv_row<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    fields
+        .into_iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{Bytes, U256},
+    };
+
+    use crate::{
+        graph::{CallGraph, MoneyFlowGraph},
+        trace::{CallKind, CallTrace, MoneyFlow},
+    };
+
+    fn call(index: usize, parent: usize, input: &[u8]) -> CallTrace {
+        let mut call = CallTrace::new(
+            index,
+            CallKind::Call,
+            0x1.cvt(),
+            0x2.cvt(),
+            Bytes::copy_from_slice(input),
+            U256::ZERO,
+        );
+        call.parent = parent;
+        call
+    }
+
+    #[test]
+    fn test_export_call_tree() {
+        let calls = vec![
+            call(1, 0, &[0xa9, 0x05, 0x9c, 0xbb, 0x00]),
+            call(2, 1, &[]),
+            call(3, 2, &[]),
+            call(4, 1, &[]),
+        ];
+        let graph = CallGraph::from_calls(&calls);
+        assert_eq!(graph.roots().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(
+            graph.outgoing(0).map(|e| e.target).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("digraph G {"));
+        assert!(dot.contains("\"selector\"=\"0xa9059cbb\""));
+        assert!(dot.contains("n1 -> n2"));
+
+        let mut graphml = Vec::new();
+        graph.write_graphml(&mut graphml).unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert_eq!(graphml.matches("<node ").count(), 4);
+        assert_eq!(graphml.matches("<edge ").count(), 3);
+    }
+
+    #[test]
+    fn test_export_money_flow_csv() {
+        let flows = vec![MoneyFlow::ether(
+            1,
+            0x1.cvt(),
+            0x2.cvt(),
+            U256::from(1),
+            vec![1, 2],
+        )];
+        let graph = MoneyFlowGraph::from_flows(&flows);
+        let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+        graph.write_csv(&mut nodes, &mut edges).unwrap();
+        let nodes = String::from_utf8(nodes).unwrap();
+        let edges = String::from_utf8(edges).unwrap();
+        assert_eq!(nodes.lines().count(), 3);
+        assert_eq!(nodes.lines().next(), Some("id,address"));
+        let lines: Vec<&str> = edges.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("source,target,index,kind"));
+        assert!(lines[1].starts_with("0,1,1,ETH,TRANSFER,"));
+        assert!(lines[1].ends_with(",false,1/2"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+pub mod call_tree;
+pub mod export;
+pub mod money_flow;
+
+pub use call_tree::{CallGraph, CallNode};
+pub use money_flow::{MoneyFlowGraph, NetFlow, NetFlowGraph};
+
+/// A directed multigraph whose nodes and edges carry data.
+/// Nodes are referred to by their positions in `nodes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Graph<N, E> {
+    pub nodes: Vec<N>,
+    /// Edges in the order they are added.
+    pub edges: Vec<Edge<E>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edge<E> {
+    pub source: usize,
+    pub target: usize,
+    pub data: E,
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node and return its position.
+    pub fn add_node(&mut self, node: N) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, source: usize, target: usize, data: E) {
+        assert!(
+            source < self.nodes.len() && target < self.nodes.len(),
+            "bug: edge to unknown node"
+        );
+        self.edges.push(Edge {
+            source,
+            target,
+            data,
+        });
+    }
+
+    pub fn outgoing(&self, node: usize) -> impl Iterator<Item = &Edge<E>> {
+        self.edges.iter().filter(move |e| e.source == node)
+    }
+
+    pub fn incoming(&self, node: usize) -> impl Iterator<Item = &Edge<E>> {
+        self.edges.iter().filter(move |e| e.target == node)
+    }
+}
+
+/// Named attributes of graph nodes or edges, written by the exporters.
+pub trait Attributes {
+    /// Names of the attributes, the same for all values of the type.
+    fn keys() -> &'static [&'static str];
+
+    /// Attribute values in the order of `keys`, empty for missing values.
+    fn values(&self) -> Vec<String>;
+
+    /// Short text to display.
+    fn label(&self) -> String;
+}
+
+impl Attributes for () {
+    fn keys() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn values(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn label(&self) -> String {
+        String::new()
+    }
+}
+
+pub(crate) fn optional<T: Display>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use libsofl_core::engine::types::{Address, U256};
+
+use crate::trace::{Asset, MoneyFlow};
+
+use super::{optional, Attributes, Graph};
+
+/// Money flow graph with an edge per money flow.
+/// Nodes are addresses, including the zero address for mints and burns.
+pub type MoneyFlowGraph = Graph<Address, MoneyFlow>;
+
+/// Money flow graph with an edge per asset per address pair, carrying the net flow.
+pub type NetFlowGraph = Graph<Address, NetFlow>;
+
+/// Net amount of an asset moved from the source to the target of the edge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetFlow {
+    pub asset: Asset,
+    pub amount: U256,
+    /// Number of money flows in both directions aggregated into the edge.
+    pub flows: usize,
+}
+
+impl MoneyFlowGraph {
+    /// Build the graph from money flows. Nodes are added in order of appearance.
+    pub fn from_flows<'a>(
+        flows: impl IntoIterator<Item = &'a MoneyFlow>,
+    ) -> Self {
+        let mut graph = Self::new();
+        let mut nodes = HashMap::new();
+        let mut node = |graph: &mut Self, address: Address| {
+            *nodes
+                .entry(address)
+                .or_insert_with(|| graph.add_node(address))
+        };
+        for flow in flows {
+            let source = node(&mut graph, flow.from);
+            let target = node(&mut graph, flow.to);
+            graph.add_edge(source, target, flow.clone());
+        }
+        graph
+    }
+
+    /// Aggregate the money flows between each address pair into the net flow of each asset.
+    /// Reverted flows are ignored, and pairs whose flows cancel out have no edge.
+    /// Nodes are kept as they are.
+    pub fn net(&self) -> NetFlowGraph {
+        // (lower node, higher node, asset) -> (amount lower to higher, amount higher to lower, flows)
+        let mut keys = Vec::new();
+        let mut totals: HashMap<(usize, usize, Asset), (U256, U256, usize)> =
+            HashMap::new();
+        for edge in self.edges.iter().filter(|e| !e.data.reverted) {
+            let (lower, higher, forward) = if edge.source <= edge.target {
+                (edge.source, edge.target, true)
+            } else {
+                (edge.target, edge.source, false)
+            };
+            let key = (lower, higher, edge.data.asset());
+            let total = totals.entry(key).or_insert_with(|| {
+                keys.push(key);
+                Default::default()
+            });
+            if forward {
+                total.0 = total.0.saturating_add(edge.data.amount);
+            } else {
+                total.1 = total.1.saturating_add(edge.data.amount);
+            }
+            total.2 += 1;
+        }
+
+        let mut graph = NetFlowGraph {
+            nodes: self.nodes.clone(),
+            edges: Vec::new(),
+        };
+        for key @ (lower, higher, asset) in keys {
+            let (forward, backward, flows) = totals[&key];
+            let (source, target, amount) = if forward >= backward {
+                (lower, higher, forward - backward)
+            } else {
+                (higher, lower, backward - forward)
+            };
+            if !amount.is_zero() {
+                graph.add_edge(
+                    source,
+                    target,
+                    NetFlow {
+                        asset,
+                        amount,
+                        flows,
+                    },
+                );
+            }
+        }
+        graph
+    }
+}
+
+impl Attributes for Address {
+    fn keys() -> &'static [&'static str] {
+        &["address"]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+
+    fn label(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Attributes for MoneyFlow {
+    fn keys() -> &'static [&'static str] {
+        &[
+            "index",
+            "kind",
+            "action",
+            "event",
+            "token",
+            "amount",
+            "value",
+            "token_id",
+            "reverted",
+            "call_path",
+        ]
+    }
+
+    fn values(&self) -> Vec<String> {
+        let path: Vec<String> =
+            self.call_path.iter().map(|i| i.to_string()).collect();
+        vec![
+            self.index.to_string(),
+            self.kind.name().to_string(),
+            self.action.name().to_string(),
+            optional(&self.event),
+            optional(&self.token),
+            self.amount.to_string(),
+            optional(&self.value),
+            optional(&self.token_id),
+            self.reverted.to_string(),
+            path.join("/"),
+        ]
+    }
+
+    fn label(&self) -> String {
+        format!("{} {}", self.amount, self.asset())
+    }
+}
+
+impl Attributes for NetFlow {
+    fn keys() -> &'static [&'static str] {
+        &["kind", "token", "token_id", "amount", "flows"]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.asset.kind.name().to_string(),
+            optional(&self.asset.token),
+            optional(&self.asset.token_id),
+            self.amount.to_string(),
+            self.flows.to_string(),
+        ]
+    }
+
+    fn label(&self) -> String {
+        format!("{} {}", self.amount, self.asset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{Address, U256},
+    };
+
+    use crate::{
+        events::TokenTransfer,
+        trace::{MoneyFlow, TokenKind},
+    };
+
+    use super::MoneyFlowGraph;
+
+    #[test]
+    fn test_net_flow() {
+        let (a, b, c): (Address, Address, Address) =
+            (0x1.cvt(), 0x2.cvt(), 0x3.cvt());
+        let token: Address = 0x10.cvt();
+        let erc20 = |from, to, amount: u64| {
+            TokenTransfer::new(
+                "Transfer",
+                TokenKind::Erc20,
+                from,
+                to,
+                U256::from(amount),
+                None,
+            )
+        };
+        let mut flows = vec![
+            MoneyFlow::ether(1, a, b, U256::from(10), vec![1]),
+            MoneyFlow::token(2, token, erc20(a, b, 5), vec![1, 2]),
+            MoneyFlow::ether(3, b, a, U256::from(4), vec![1, 3]),
+            MoneyFlow::token(4, token, erc20(b, c, 7), vec![1, 2]),
+            MoneyFlow::token(5, token, erc20(c, b, 7), vec![1, 2]),
+            MoneyFlow::ether(6, a, c, U256::from(1), vec![1, 4]),
+        ];
+        flows[5].reverted = true;
+
+        let graph = MoneyFlowGraph::from_flows(&flows);
+        assert_eq!(graph.nodes, vec![a, b, c]);
+        assert_eq!(graph.edges.len(), flows.len());
+        assert_eq!(graph.outgoing(0).count(), 3);
+
+        let net = graph.net();
+        assert_eq!(net.nodes, graph.nodes);
+        assert_eq!(net.edges.len(), 2);
+        let eth = &net.edges[0];
+        assert_eq!((eth.source, eth.target), (0, 1));
+        assert_eq!(eth.data.asset.kind, TokenKind::Eth);
+        assert_eq!(eth.data.amount, U256::from(6));
+        assert_eq!(eth.data.flows, 2);
+        let erc20 = &net.edges[1];
+        assert_eq!((erc20.source, erc20.target), (0, 1));
+        assert_eq!(erc20.data.asset.token, Some(token));
+        assert_eq!(erc20.data.amount, U256::from(5));
+    }
+}
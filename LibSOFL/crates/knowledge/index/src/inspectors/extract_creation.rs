@@ -2,14 +2,15 @@ use libsofl_core::engine::{
     inspector::EvmInspector,
     state::BcState,
     types::{
-        Address, Bytes, CreateInputs, EVMData, Gas, Inspector,
-        InstructionResult, U256,
+        Address, Bytes, CreateInputs, EVMData, ExecutionResult, Gas, Inspector,
+        InstructionResult, TxEnv, U256,
     },
 };
 
 #[derive(Default)]
 pub struct ExtractCreationInspector {
     pub created: Vec<(Address, bool)>, // (created address, whether destruct) ordered
+    tx_start: usize, // length of `created` when the current transaction starts
 }
 
 impl<BS: BcState> Inspector<BS> for ExtractCreationInspector {
@@ -47,7 +48,24 @@ impl<BS: BcState> Inspector<BS> for ExtractCreationInspector {
     }
 }
 
-impl<BS: BcState> EvmInspector<BS> for ExtractCreationInspector {}
+impl<BS: BcState> EvmInspector<BS> for ExtractCreationInspector {
+    fn transaction(&mut self, _tx: &TxEnv, _state: &BS) -> bool {
+        self.tx_start = self.created.len();
+        true
+    }
+
+    fn transaction_end(
+        &mut self,
+        _tx: &TxEnv,
+        _state: &BS,
+        result: &ExecutionResult,
+    ) {
+        // creations and destructions of a failed transaction do not take effect
+        if !result.is_success() {
+            self.created.truncate(self.tx_start);
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -4,7 +4,13 @@ use libsofl_core::engine::{
     inspector::EvmInspector,
     state::BcState,
     types::{
-        Address, Bytes, CallInputs, EVMData, Gas, Inspector, InstructionResult,
+        Address,
+        Bytes,
+        CallInputs,
+        EVMData,
+        Gas,
+        Inspector,
+        InstructionResult,
         // B256, U256, Database,
     },
 };
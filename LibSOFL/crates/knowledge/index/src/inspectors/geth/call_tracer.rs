@@ -0,0 +1,380 @@
+use alloy_primitives::U64;
+use libsofl_core::engine::{
+    inspector::EvmInspector,
+    state::BcState,
+    types::{
+        Address, Bytes, CallInputs, CreateInputs, EVMData, ExecutionResult,
+        Gas, Inspector, InstructionResult, TxEnv, B256, U256,
+    },
+};
+use libsofl_utils::solidity::revert::RevertReason;
+use revm::interpreter::CallScheme;
+use serde::{Deserialize, Serialize};
+
+use crate::trace::CallKind;
+
+use super::geth_error;
+
+/// Options of geth's `callTracer`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallTracerConfig {
+    /// Only trace the outermost call frame.
+    pub only_top_call: bool,
+    /// Attach logs to the call frames emitting them.
+    pub with_log: bool,
+}
+
+/// A log in the `callTracer` output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    /// Number of sub-calls of the frame before the log is emitted.
+    pub position: U64,
+}
+
+/// A call frame in the `callTracer` output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub kind: CallKind,
+    pub from: Address,
+    pub gas: U64,
+    pub gas_used: U64,
+    /// The callee, the created contract, or the beneficiary of a selfdestruct.
+    /// `None` for failed creations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    pub input: Bytes,
+    /// Return data of successful or reverted frames, if not empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Message of `Error(string)` in the revert data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+    /// Ether transferred, `None` for delegate calls and static calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+}
+
+impl CallFrame {
+    fn new(
+        kind: CallKind,
+        from: Address,
+        to: Address,
+        input: Bytes,
+        value: Option<U256>,
+        gas: u64,
+    ) -> Self {
+        Self {
+            kind,
+            from,
+            gas: U64::from(gas),
+            gas_used: U64::ZERO,
+            to: Some(to),
+            input,
+            output: None,
+            error: None,
+            revert_reason: None,
+            calls: Vec::new(),
+            logs: Vec::new(),
+            value,
+        }
+    }
+
+    fn end(&mut self, result: InstructionResult, output: Bytes, gas_used: u64) {
+        self.gas_used = U64::from(gas_used);
+        self.error = geth_error(result);
+        if self.error.is_some() && self.kind.is_create() {
+            self.to = None;
+        }
+        let revert = result == InstructionResult::Revert;
+        if (self.error.is_none() || revert) && !output.is_empty() {
+            if revert {
+                if let RevertReason::Error(reason) =
+                    RevertReason::decode(&output)
+                {
+                    self.revert_reason = Some(reason);
+                }
+            }
+            self.output = Some(output);
+        }
+    }
+
+    /// Drop the logs of the frame and its descendants if the frame fails.
+    fn clear_failed_logs(&mut self, parent_failed: bool) {
+        let failed = parent_failed || self.error.is_some();
+        if failed {
+            self.logs.clear();
+        }
+        self.calls
+            .iter_mut()
+            .for_each(|c| c.clear_failed_logs(failed));
+    }
+}
+
+/// Inspector producing the output of geth's `callTracer`, a tree of call frames
+/// of the last transaction it inspects.
+#[derive(Clone, Debug, Default)]
+pub struct CallTracer {
+    pub config: CallTracerConfig,
+    /// The outermost call frame, available after the transaction ends.
+    pub root: Option<CallFrame>,
+    stack: Vec<CallFrame>,
+    /// Depth of the call frames ignored with `only_top_call`.
+    ignored: usize,
+    /// Gas limit of the transaction.
+    gas_limit: u64,
+}
+
+impl CallTracer {
+    pub fn new(config: CallTracerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_frame(self) -> Option<CallFrame> {
+        self.root
+    }
+
+    fn push(&mut self, frame: CallFrame) {
+        if self.config.only_top_call && !self.stack.is_empty() {
+            self.ignored += 1;
+            return;
+        }
+        self.stack.push(frame);
+    }
+
+    fn pop(&mut self, result: InstructionResult, output: Bytes, gas_used: u64) {
+        if self.ignored > 0 {
+            self.ignored -= 1;
+            return;
+        }
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.end(result, output, gas_used);
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+impl<BS: BcState> Inspector<BS> for CallTracer {
+    fn log(
+        &mut self,
+        _evm_data: &mut EVMData<'_, BS>,
+        address: &Address,
+        topics: &[B256],
+        data: &Bytes,
+    ) {
+        if !self.config.with_log || self.ignored > 0 {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push(CallLog {
+                address: *address,
+                topics: topics.to_vec(),
+                data: data.clone(),
+                position: U64::from(frame.calls.len()),
+            });
+        }
+    }
+
+    fn call(
+        &mut self,
+        _evm_data: &mut EVMData<'_, BS>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        let scheme = inputs.context.scheme;
+        let value = match scheme {
+            CallScheme::Call | CallScheme::CallCode => {
+                Some(inputs.transfer.value)
+            }
+            CallScheme::DelegateCall | CallScheme::StaticCall => None,
+        };
+        // geth reports the contract whose code is run as the callee, and the contract
+        // making the delegate call (instead of the inherited caller) as the caller
+        let from = match scheme {
+            CallScheme::DelegateCall => inputs.context.address,
+            _ => inputs.context.caller,
+        };
+        self.push(CallFrame::new(
+            scheme.into(),
+            from,
+            inputs.contract,
+            inputs.input.clone(),
+            value,
+            inputs.gas_limit,
+        ));
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, BS>,
+        _inputs: &CallInputs,
+        gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.pop(ret, out.clone(), gas.spend());
+        (ret, gas, out)
+    }
+
+    fn create(
+        &mut self,
+        evm_data: &mut EVMData<'_, BS>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        let nonce = evm_data.journaled_state.account(inputs.caller).info.nonce;
+        self.push(CallFrame::new(
+            inputs.scheme.into(),
+            inputs.caller,
+            inputs.created_address(nonce),
+            inputs.init_code.clone(),
+            Some(inputs.value),
+            inputs.gas_limit,
+        ));
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(inputs.gas_limit),
+            Bytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, BS>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.pop(ret, out.clone(), remaining_gas.spend());
+        (ret, address, remaining_gas, out)
+    }
+
+    fn selfdestruct(
+        &mut self,
+        contract: Address,
+        target: Address,
+        value: U256,
+    ) {
+        if self.config.only_top_call || self.ignored > 0 {
+            return;
+        }
+        if let Some(parent) = self.stack.last_mut() {
+            let frame = CallFrame::new(
+                CallKind::SelfDestruct,
+                contract,
+                target,
+                Bytes::new(),
+                Some(value),
+                0,
+            );
+            parent.calls.push(frame);
+        }
+    }
+}
+
+impl<BS: BcState> EvmInspector<BS> for CallTracer {
+    fn transaction(&mut self, tx: &TxEnv, _state: &BS) -> bool {
+        self.root = None;
+        self.stack.clear();
+        self.ignored = 0;
+        self.gas_limit = tx.gas_limit;
+        true
+    }
+
+    fn transaction_end(
+        &mut self,
+        _tx: &TxEnv,
+        _state: &BS,
+        result: &ExecutionResult,
+    ) {
+        // the outermost frame includes the intrinsic gas
+        if let Some(root) = self.root.as_mut() {
+            root.gas = U64::from(self.gas_limit);
+            root.gas_used = U64::from(result.gas_used());
+            root.clear_failed_logs(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::engine::types::U256;
+
+    use crate::{
+        inspectors::geth::assert_golden, testing::fixtures, trace::CallKind,
+    };
+
+    use super::{CallTracer, CallTracerConfig};
+
+    #[test]
+    fn test_call_tracer() {
+        let mut tracer = CallTracer::new(CallTracerConfig {
+            only_top_call: false,
+            with_log: true,
+        });
+        fixtures::run_nested(&mut tracer);
+        let root = tracer.into_frame().unwrap();
+        assert_eq!(root.kind, CallKind::Call);
+        assert_eq!(root.value, Some(U256::ZERO));
+        assert!(root.error.is_none());
+        assert_eq!(root.logs.len(), 1);
+        assert_eq!(root.logs[0].position.to::<u64>(), 4);
+        let kinds: Vec<_> = root.calls.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                CallKind::Create,
+                CallKind::Call,
+                CallKind::Call,
+                CallKind::Call
+            ]
+        );
+        assert_eq!(root.calls[1].logs.len(), 1);
+        let failed = &root.calls[2];
+        assert_eq!(failed.error.as_deref(), Some("execution reverted"));
+        assert_eq!(failed.revert_reason.as_deref(), Some("child failed"));
+        assert!(failed.output.is_some());
+        assert_eq!(root.calls[3].calls[0].kind, CallKind::SelfDestruct);
+        let gas_used: u64 =
+            root.calls.iter().map(|c| c.gas_used.to::<u64>()).sum();
+        assert!(root.gas_used.to::<u64>() > gas_used);
+
+        let json = serde_json::to_value(&root).unwrap();
+        assert_eq!(json["type"], "CALL");
+        assert_eq!(json["calls"][3]["calls"][0]["type"], "SELFDESTRUCT");
+        assert!(json["calls"][2].get("revertReason").is_some());
+        assert_golden("call_tracer", &json);
+    }
+
+    #[test]
+    fn test_call_tracer_only_top_call() {
+        let mut tracer = CallTracer::new(CallTracerConfig {
+            only_top_call: true,
+            with_log: false,
+        });
+        fixtures::run_nested(&mut tracer);
+        let root = tracer.into_frame().unwrap();
+        assert!(root.calls.is_empty());
+        assert!(root.logs.is_empty());
+        assert!(root.output.is_none());
+    }
+}
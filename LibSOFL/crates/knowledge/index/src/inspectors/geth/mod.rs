@@ -0,0 +1,68 @@
+//! Inspectors producing the JSON formats of geth's `debug_traceTransaction`,
+//! for comparing replayed transactions with geth, Etherscan or Tenderly traces.
+
+pub mod call_tracer;
+pub mod struct_logger;
+
+use libsofl_core::engine::types::InstructionResult;
+
+/// Error message of geth for a failed call frame or opcode, `None` if it succeeds.
+/// Errors without a geth counterpart are printed as their revm names.
+pub fn geth_error(result: InstructionResult) -> Option<String> {
+    use InstructionResult::*;
+    let error = match result {
+        Continue | Stop | Return | SelfDestruct => return None,
+        Revert => "execution reverted",
+        OutOfGas | MemoryOOG | MemoryLimitOOG | PrecompileOOG
+        | InvalidOperandOOG => "out of gas",
+        CallTooDeep => "max call depth exceeded",
+        OutOfFund => "insufficient balance for transfer",
+        CallNotAllowedInsideStatic | StateChangeDuringStaticCall => {
+            "write protection"
+        }
+        InvalidFEOpcode => "invalid opcode: INVALID",
+        OpcodeNotFound => "invalid opcode",
+        InvalidJump => "invalid jump destination",
+        StackUnderflow => "stack underflow",
+        StackOverflow => "stack limit reached 1024",
+        OutOfOffset => "return data out of bounds",
+        CreateCollision => "contract address collision",
+        NonceOverflow => "nonce uint64 overflow",
+        CreateContractSizeLimit => "max code size exceeded",
+        CreateContractStartingWithEF => {
+            "invalid code: must not begin with 0xef"
+        }
+        CreateInitcodeSizeLimit => "max initcode size exceeded",
+        _ => return Some(format!("{:?}", result)),
+    };
+    Some(error.to_string())
+}
+
+/// Compare the value with the golden file `testdata/geth/<name>.json`.
+/// A missing golden file fails the test; run with `UPDATE_GOLDEN=1` to (re)write it.
+#[cfg(test)]
+pub(crate) fn assert_golden(name: &str, value: &serde_json::Value) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata/geth")
+        .join(format!("{}.json", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let json = serde_json::to_string_pretty(value).unwrap();
+        std::fs::write(&path, json + "\n").unwrap();
+        return;
+    }
+    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read golden file {}: {}, run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            e
+        )
+    });
+    let golden: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value,
+        &golden,
+        "output differs from {}, rerun with UPDATE_GOLDEN=1 if intended",
+        path.display()
+    );
+}
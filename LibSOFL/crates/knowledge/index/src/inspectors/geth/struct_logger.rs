@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy_primitives::hex;
+use libsofl_core::engine::{
+    inspector::EvmInspector,
+    state::BcState,
+    types::{
+        opcode::OPCODE_JUMPMAP, Address, Bytes, CallInputs, CreateInputs,
+        EVMData, ExecutionResult, Gas, Inspector, InstructionResult,
+        Interpreter, TxEnv, U256,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::geth_error;
+
+/// Options of geth's default struct logger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StructLoggerConfig {
+    pub enable_memory: bool,
+    pub disable_stack: bool,
+    pub disable_storage: bool,
+    pub enable_return_data: bool,
+}
+
+impl Default for StructLoggerConfig {
+    /// Same as geth: stack and storage without memory and return data.
+    fn default() -> Self {
+        Self {
+            enable_memory: false,
+            disable_stack: false,
+            disable_storage: false,
+            enable_return_data: false,
+        }
+    }
+}
+
+/// An executed opcode in the struct logger output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: u64,
+    pub op: String,
+    /// Gas remaining before the opcode.
+    pub gas: u64,
+    /// Gas used by the opcode. Unlike geth, `CALL` and `CREATE` opcodes include
+    /// the gas used by the sub-call instead of the gas forwarded to it.
+    pub gas_cost: u64,
+    pub depth: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Stack before the opcode from bottom to top, as hex numbers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    /// Return data of the last sub-call, as hex bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<String>,
+    /// Memory before the opcode, as hex words without `0x`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    /// Slots of the contract accessed so far, as hex words without `0x`,
+    /// on `SLOAD` and `SSTORE` only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub refund: u64,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// Output of the struct logger, the same as geth's `debug_traceTransaction` without a tracer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogResult {
+    /// Gas used by the transaction.
+    pub gas: u64,
+    pub failed: bool,
+    /// Output of the transaction, as hex bytes without `0x`.
+    pub return_value: String,
+    pub struct_logs: Vec<StructLog>,
+}
+
+/// Inspector producing the output of geth's struct logger for the last transaction it inspects.
+#[derive(Clone, Debug, Default)]
+pub struct StructLogger {
+    pub config: StructLoggerConfig,
+    pub result: StructLogResult,
+    /// Slots accessed so far by each contract.
+    storage: HashMap<Address, BTreeMap<U256, U256>>,
+    depth: u64,
+    /// Indices of the opcodes being executed, one per call frame, as the
+    /// `step_end` of a call opcode comes after the opcodes of the sub-call.
+    steps: Vec<usize>,
+}
+
+impl StructLogger {
+    pub fn new(config: StructLoggerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_result(self) -> StructLogResult {
+        self.result
+    }
+
+    fn hex_word(word: U256) -> String {
+        hex::encode(word.to_be_bytes::<32>())
+    }
+}
+
+impl<BS: BcState> Inspector<BS> for StructLogger {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        _evm_data: &mut EVMData<'_, BS>,
+    ) {
+        let op = interp.current_opcode();
+        let stack = (!self.config.disable_stack).then(|| {
+            interp
+                .stack()
+                .data()
+                .iter()
+                .map(|v| format!("{:#x}", v))
+                .collect()
+        });
+        let memory = self.config.enable_memory.then(|| {
+            interp
+                .shared_memory
+                .context_memory()
+                .chunks(32)
+                .map(hex::encode)
+                .collect()
+        });
+        let return_data = self
+            .config
+            .enable_return_data
+            .then(|| hex::encode_prefixed(&interp.return_data_buffer));
+        let name = match OPCODE_JUMPMAP[op as usize] {
+            Some(name) => name.to_string(),
+            None => format!("opcode {:#x} not defined", op),
+        };
+        self.result.struct_logs.push(StructLog {
+            pc: interp.program_counter() as u64,
+            op: name,
+            gas: interp.gas.remaining(),
+            gas_cost: 0,
+            depth: self.depth,
+            error: None,
+            stack,
+            return_data,
+            memory,
+            storage: None,
+            refund: interp.gas.refunded() as u64,
+        });
+        self.steps.push(self.result.struct_logs.len() - 1);
+    }
+
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        _evm_data: &mut EVMData<'_, BS>,
+    ) {
+        let Some(log) = self
+            .steps
+            .pop()
+            .and_then(|i| self.result.struct_logs.get_mut(i))
+        else {
+            return;
+        };
+        log.gas_cost = log.gas.saturating_sub(interp.gas.remaining());
+        if interp.instruction_result != InstructionResult::Revert {
+            log.error = geth_error(interp.instruction_result);
+        }
+
+        // the value of SLOAD is known after the opcode is executed,
+        // while the stack of SSTORE is popped
+        if self.config.disable_storage || log.error.is_some() {
+            return;
+        }
+        let Some(stack) = log.stack.as_ref() else {
+            return;
+        };
+        let parse = |s: &String| s.parse::<U256>().unwrap_or_default();
+        let entry = match log.op.as_str() {
+            "SLOAD" => stack
+                .last()
+                .map(|slot| (parse(slot), interp.stack().peek(0).ok())),
+            "SSTORE" if stack.len() >= 2 => {
+                let value = parse(&stack[stack.len() - 2]);
+                Some((parse(&stack[stack.len() - 1]), Some(value)))
+            }
+            _ => None,
+        };
+        if let Some((slot, Some(value))) = entry {
+            let storage =
+                self.storage.entry(interp.contract.address).or_default();
+            storage.insert(slot, value);
+            log.storage = Some(
+                storage
+                    .iter()
+                    .map(|(k, v)| (Self::hex_word(*k), Self::hex_word(*v)))
+                    .collect(),
+            );
+        }
+    }
+
+    fn call(
+        &mut self,
+        _evm_data: &mut EVMData<'_, BS>,
+        _inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.depth += 1;
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, BS>,
+        _inputs: &CallInputs,
+        gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.depth -= 1;
+        (ret, gas, out)
+    }
+
+    fn create(
+        &mut self,
+        _evm_data: &mut EVMData<'_, BS>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.depth += 1;
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(inputs.gas_limit),
+            Bytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, BS>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.depth -= 1;
+        (ret, address, remaining_gas, out)
+    }
+}
+
+impl<BS: BcState> EvmInspector<BS> for StructLogger {
+    fn transaction(&mut self, _tx: &TxEnv, _state: &BS) -> bool {
+        self.result = StructLogResult::default();
+        self.storage.clear();
+        self.depth = 0;
+        self.steps.clear();
+        true
+    }
+
+    fn transaction_end(
+        &mut self,
+        _tx: &TxEnv,
+        _state: &BS,
+        result: &ExecutionResult,
+    ) {
+        self.result.gas = result.gas_used();
+        self.result.failed = !result.is_success();
+        self.result.return_value = result
+            .output()
+            .map(|output| hex::encode(output))
+            .unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inspectors::geth::assert_golden, testing::fixtures};
+
+    use super::{StructLogger, StructLoggerConfig};
+
+    #[test]
+    fn test_struct_logger() {
+        let mut logger = StructLogger::new(StructLoggerConfig::default());
+        fixtures::run_nested(&mut logger);
+        let result = logger.into_result();
+        assert!(!result.failed);
+        assert!(result.return_value.is_empty());
+        let logs = &result.struct_logs;
+        assert_eq!(logs[0].depth, 1);
+        assert_eq!(logs[0].pc, 0);
+        assert_eq!(logs[0].op, "PUSH1");
+        assert!(logs.iter().all(|l| l.memory.is_none()));
+        assert_eq!(logs.iter().map(|l| l.depth).max(), Some(2));
+        assert!(logs.iter().any(|l| l.op == "REVERT" && l.depth == 2));
+        let sstore = logs.iter().find(|l| l.op == "SSTORE").unwrap();
+        assert_eq!(
+            sstore.storage.as_ref().unwrap().values().next().unwrap(),
+            &format!("{:064x}", 42)
+        );
+        for window in logs.windows(2) {
+            if window[0].depth == window[1].depth {
+                assert_eq!(window[0].gas - window[0].gas_cost, window[1].gas);
+            }
+        }
+
+        assert_golden("struct_logger", &serde_json::to_value(&result).unwrap());
+    }
+
+    #[test]
+    fn test_struct_logger_memory() {
+        let mut logger = StructLogger::new(StructLoggerConfig {
+            enable_memory: true,
+            disable_stack: true,
+            disable_storage: true,
+            ..Default::default()
+        });
+        fixtures::run_nested(&mut logger);
+        let logs = logger.into_result().struct_logs;
+        assert!(logs
+            .iter()
+            .all(|l| l.stack.is_none() && l.storage.is_none()));
+        // solidity stores the free memory pointer first
+        let mstore = logs.iter().position(|l| l.op == "MSTORE").unwrap();
+        assert_eq!(logs[mstore + 1].memory.as_ref().unwrap().len(), 3);
+    }
+}
@@ -1,5 +1,4 @@
 pub mod extract_creation;
 pub mod extract_invocation;
-pub mod extract_money_flow;
-pub mod extract_mf_and_fc;
-pub mod extract_mf_and_fc_and_op;
\ No newline at end of file
+pub mod geth;
+pub mod tx_tracer;
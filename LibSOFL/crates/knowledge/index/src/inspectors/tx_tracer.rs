@@ -0,0 +1,939 @@
+// single-pass transaction tracer: money flow, call tree, opcodes, storage accesses and logs
+use std::sync::Arc;
+
+use libsofl_core::engine::{
+    inspector::EvmInspector,
+    state::BcState,
+    storage_access::StorageAccessInspector,
+    types::{
+        opcode, Address, Bytes, CallInputs, CreateInputs, EVMData,
+        ExecutionResult, Gas, Inspector, InstructionResult, TxEnv, B256, U256,
+    },
+};
+use libsofl_utils::solidity::revert::RevertReason;
+use revm::inspectors::GasInspector;
+use revm::interpreter::{CallScheme, Interpreter};
+
+use crate::{
+    events::{transfers_in_log, EventRegistry},
+    selectors::SelectorDb,
+    trace::{
+        CallKind, CallTrace, LogRecord, MoneyFlow, Opcode, RevertedFlows,
+        SlotChange, StepDetail, StepRecord, StorageAccess, TxMeta, TxTrace,
+    },
+};
+
+/// What `TxTracer` traces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceConfig {
+    pub money_flow: bool,
+    pub call_tree: bool,
+    /// Opcodes are attached to call frames, so tracing opcodes implies tracing the call tree.
+    pub opcodes: bool,
+    /// Detail of each opcode, if opcodes are traced.
+    pub step_detail: StepDetail,
+    pub storage: bool,
+    pub logs: bool,
+    /// What to do with money flows inside reverted call frames.
+    pub reverted_flows: RevertedFlows,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            money_flow: true,
+            call_tree: true,
+            opcodes: false,
+            step_detail: StepDetail::Basic,
+            storage: false,
+            logs: false,
+            reverted_flows: RevertedFlows::default(),
+        }
+    }
+}
+
+impl TraceConfig {
+    /// Trace everything, with rich steps including the top 4 stack words.
+    pub fn all() -> Self {
+        Self {
+            money_flow: true,
+            call_tree: true,
+            opcodes: true,
+            step_detail: StepDetail::Rich { stack_top: 4 },
+            storage: true,
+            logs: true,
+            reverted_flows: RevertedFlows::default(),
+        }
+    }
+}
+
+/// A call frame on the stack, with the number of records when it starts.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    index: usize,
+    flows: usize,
+    logs: usize,
+    /// Start of the opcode range being executed in this frame.
+    segment: usize,
+}
+
+/// Inspector tracing transactions in a single pass.
+/// Call frames are always tracked, since money flows, storage accesses and logs refer to them.
+/// When several transactions are executed, each one gets its own trace with indices
+/// starting from 1, via the transaction hooks of `EvmInspector`.
+#[derive(Clone, Debug, Default)]
+pub struct TxTracer {
+    pub config: TraceConfig,
+    /// Decoders of token events, the standard ones by default.
+    pub registry: EventRegistry,
+    /// Decoders of function calls, no call is decoded if `None`.
+    pub selectors: Option<Arc<SelectorDb>>,
+    /// Traces of the finished transactions.
+    pub traces: Vec<TxTrace>,
+    /// Trace of the transaction being executed.
+    pub trace: TxTrace,
+    frames: Vec<Frame>,
+    /// Storage accesses, recorded if `config.storage` is set.
+    storage: StorageAccessInspector,
+    /// Index of the rich step of the opcode being executed in each call frame,
+    /// the gas remaining before it, and whether the old value of the accessed slot is known,
+    /// as the `step_end` of a call opcode comes after the opcodes of the sub-call.
+    pending_steps: Vec<(usize, u64, bool)>,
+    gas_inspector: GasInspector,
+}
+
+impl TxTracer {
+    pub fn new(config: TraceConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Decode token events with the given registry.
+    pub fn with_registry(mut self, registry: EventRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Decode message calls with the given selector database.
+    pub fn with_selectors(mut self, selectors: Arc<SelectorDb>) -> Self {
+        self.selectors = Some(selectors);
+        self
+    }
+
+    /// Take the trace of the last transaction.
+    pub fn into_trace(self) -> TxTrace {
+        self.into_traces().pop().unwrap_or_default()
+    }
+
+    /// Take the traces of all transactions in order.
+    pub fn into_traces(mut self) -> Vec<TxTrace> {
+        // the transaction hooks are not called if the tracer is used as a plain revm inspector
+        if self.trace != TxTrace::default() || !self.storage.accesses.is_empty()
+        {
+            let trace = self.finish();
+            self.traces.push(trace);
+        }
+        self.traces
+    }
+
+    /// Take the trace being recorded, dropping the call tree if it is not traced.
+    fn finish(&mut self) -> TxTrace {
+        let mut trace = std::mem::take(&mut self.trace);
+        trace.storage = std::mem::take(&mut self.storage.accesses)
+            .into_iter()
+            .enumerate()
+            .map(|(i, a)| StorageAccess {
+                index: i + 1,
+                call_index: a.call_index,
+                address: a.address,
+                slot: a.slot,
+                old: a.old,
+                value: a.new,
+                write: a.write,
+                reverted: a.reverted,
+            })
+            .collect();
+        if !self.config.call_tree && !self.config.opcodes {
+            trace.calls.clear();
+        }
+        trace
+    }
+
+    fn call_index(&self) -> usize {
+        self.frames.last().map(|f| f.index).unwrap_or_default()
+    }
+
+    /// Close the opcode range being executed in the call frame on top of the stack.
+    fn close_segment(&mut self) {
+        let end = self.trace.opcodes.len();
+        if let Some(frame) = self.frames.last_mut() {
+            if frame.segment < end {
+                self.trace.calls[frame.index - 1]
+                    .opcodes
+                    .push(frame.segment..end);
+            }
+            frame.segment = end;
+        }
+    }
+
+    fn push_frame(&mut self, mut call: CallTrace) {
+        self.close_segment();
+        call.index = self.trace.calls.len() + 1;
+        call.parent = self.call_index();
+        self.frames.push(Frame {
+            index: call.index,
+            flows: self.trace.money_flows.len(),
+            logs: self.trace.logs.len(),
+            segment: self.trace.opcodes.len(),
+        });
+        self.trace.calls.push(call);
+    }
+
+    fn pop_frame(
+        &mut self,
+        output: Bytes,
+        result: InstructionResult,
+        gas_used: u64,
+    ) {
+        self.close_segment();
+        let frame = self.frames.pop().expect("bug: empty call stack");
+        if let Some(parent) = self.frames.last_mut() {
+            parent.segment = self.trace.opcodes.len();
+        }
+        let call = &mut self.trace.calls[frame.index - 1];
+        call.output = output;
+        call.result = result;
+        call.gas_used = gas_used;
+        if result == InstructionResult::Revert && !call.output.is_empty() {
+            let reason = match &self.selectors {
+                Some(selectors) => selectors.errors().decode(&call.output),
+                None => RevertReason::decode(&call.output),
+            };
+            call.revert_reason = Some(reason.to_string());
+        }
+        if let Some(selectors) = &self.selectors {
+            if !call.kind.is_create() && call.kind != CallKind::SelfDestruct {
+                call.decoded =
+                    selectors.decode(&call.input, &call.output, result.is_ok());
+            }
+        }
+
+        // the call frame and all its descendants are reverted
+        if !result.is_ok() {
+            self.trace.calls[frame.index - 1..]
+                .iter_mut()
+                .for_each(|c| c.reverted = true);
+            self.config
+                .reverted_flows
+                .apply(&mut self.trace.money_flows, frame.flows);
+            self.trace.logs[frame.logs..]
+                .iter_mut()
+                .for_each(|l| l.reverted = true);
+        }
+    }
+
+    fn add_ether_flow(&mut self, from: Address, to: Address, value: U256) {
+        if !self.config.money_flow || value.is_zero() {
+            return;
+        }
+        let flow = MoneyFlow::ether(
+            self.trace.money_flows.len() + 1,
+            from,
+            to,
+            value,
+            self.frames.iter().map(|f| f.index).collect(),
+        );
+        self.trace.money_flows.push(flow);
+    }
+}
+
+impl<BS: BcState> Inspector<BS> for TxTracer {
+    fn initialize_interp(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        evm_data: &mut EVMData<'_, BS>,
+    ) {
+        if self.config.opcodes {
+            self.gas_inspector.initialize_interp(interp, evm_data);
+        }
+    }
+
+    fn step(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        evm_data: &mut EVMData<'_, BS>,
+    ) {
+        let op = interp.current_opcode();
+        if self.config.opcodes {
+            self.trace.opcodes.push(Opcode {
+                op,
+                pc: interp.program_counter() as u32,
+                gas_remaining: self.gas_inspector.gas_remaining(),
+                depth: self.frames.len() as u32,
+            });
+            self.gas_inspector.step(interp, evm_data);
+
+            if let StepDetail::Rich { stack_top } = self.config.step_detail {
+                let stack = interp.stack();
+                let mut old_known = false;
+                let storage = match op {
+                    opcode::SLOAD | opcode::SSTORE => {
+                        stack.peek(0).ok().map(|slot| {
+                            // the old value is the present value if the slot is loaded,
+                            // otherwise the original value after the opcode is executed
+                            let old = evm_data
+                                .journaled_state
+                                .state
+                                .get(&interp.contract.address)
+                                .and_then(|a| a.storage.get(&slot))
+                                .map(|s| s.present_value());
+                            old_known = old.is_some();
+                            SlotChange {
+                                slot,
+                                old: old.unwrap_or_default(),
+                                new: U256::ZERO,
+                            }
+                        })
+                    }
+                    _ => None,
+                };
+                let step = StepRecord {
+                    gas_cost: 0,
+                    stack: stack
+                        .data()
+                        .iter()
+                        .rev()
+                        .take(stack_top)
+                        .copied()
+                        .collect(),
+                    memory_size: interp.shared_memory.len(),
+                    storage,
+                };
+                self.pending_steps.push((
+                    self.trace.steps.len(),
+                    interp.gas.remaining(),
+                    old_known,
+                ));
+                self.trace.steps.push(step);
+            }
+        }
+
+        if self.config.storage {
+            Inspector::<BS>::step(&mut self.storage, interp, evm_data);
+        }
+    }
+
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter<'_>,
+        evm_data: &mut EVMData<'_, BS>,
+    ) {
+        if self.config.opcodes {
+            self.gas_inspector.step_end(interp, evm_data);
+        }
+
+        if let Some((i, gas_before, old_known)) = self.pending_steps.pop() {
+            let step = &mut self.trace.steps[i];
+            // the gas of sub-calls is included in CALL and CREATE
+            step.gas_cost = gas_before.saturating_sub(interp.gas.remaining());
+            if let Some(change) = step.storage.as_mut() {
+                let slot = evm_data
+                    .journaled_state
+                    .state
+                    .get(&interp.contract.address)
+                    .and_then(|a| a.storage.get(&change.slot));
+                if let Some(slot) = slot {
+                    if !old_known {
+                        change.old = slot.original_value();
+                    }
+                    change.new = slot.present_value();
+                }
+            }
+        }
+
+        if self.config.storage {
+            Inspector::<BS>::step_end(&mut self.storage, interp, evm_data);
+        }
+    }
+
+    fn call(
+        &mut self,
+        evm_data: &mut EVMData<'_, BS>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if self.config.storage {
+            Inspector::<BS>::call(&mut self.storage, evm_data, inputs);
+        }
+        self.push_frame(CallTrace::new(
+            0,
+            inputs.context.scheme.into(),
+            inputs.context.caller,
+            inputs.context.address,
+            inputs.input.clone(),
+            inputs.transfer.value,
+        ));
+
+        // only CALL moves ether, CALLCODE transfers to the caller itself
+        if inputs.context.scheme == CallScheme::Call {
+            self.add_ether_flow(
+                inputs.transfer.source,
+                inputs.transfer.target,
+                inputs.transfer.value,
+            );
+        }
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, BS>,
+        inputs: &CallInputs,
+        gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if self.config.storage {
+            Inspector::<BS>::call_end(
+                &mut self.storage,
+                data,
+                inputs,
+                gas,
+                ret,
+                out.clone(),
+            );
+        }
+        self.pop_frame(out.clone(), ret, gas.spend());
+        (ret, gas, out)
+    }
+
+    fn create(
+        &mut self,
+        evm_data: &mut EVMData<'_, BS>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        if self.config.storage {
+            Inspector::<BS>::create(&mut self.storage, evm_data, inputs);
+        }
+        let nonce = evm_data.journaled_state.account(inputs.caller).info.nonce;
+        let addr = inputs.created_address(nonce);
+        self.push_frame(CallTrace::new(
+            0,
+            inputs.scheme.into(),
+            inputs.caller,
+            addr,
+            inputs.init_code.clone(),
+            inputs.value,
+        ));
+        self.add_ether_flow(inputs.caller, addr, inputs.value);
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(inputs.gas_limit),
+            Bytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, BS>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        if self.config.storage {
+            Inspector::<BS>::create_end(
+                &mut self.storage,
+                data,
+                inputs,
+                ret,
+                address,
+                remaining_gas,
+                out.clone(),
+            );
+        }
+        self.pop_frame(out.clone(), ret, remaining_gas.spend());
+        (ret, address, remaining_gas, out)
+    }
+
+    fn selfdestruct(
+        &mut self,
+        contract: Address,
+        target: Address,
+        value: U256,
+    ) {
+        if self.config.storage {
+            Inspector::<BS>::selfdestruct(
+                &mut self.storage,
+                contract,
+                target,
+                value,
+            );
+        }
+        // selfdestruct has no input, output and opcodes
+        self.push_frame(CallTrace::new(
+            0,
+            CallKind::SelfDestruct,
+            contract,
+            target,
+            Bytes::new(),
+            value,
+        ));
+        self.add_ether_flow(contract, target, value);
+        self.pop_frame(Bytes::new(), InstructionResult::SelfDestruct, 0);
+    }
+
+    fn log(
+        &mut self,
+        evm_data: &mut EVMData<'_, BS>,
+        address: &Address,
+        topics: &[B256],
+        data: &Bytes,
+    ) {
+        if self.config.logs {
+            self.trace.logs.push(LogRecord {
+                index: self.trace.logs.len() + 1,
+                call_index: self.call_index(),
+                address: *address,
+                topics: topics.to_vec(),
+                data: data.clone(),
+                reverted: false,
+            });
+        }
+
+        if self.config.money_flow {
+            let call_path: Vec<usize> =
+                self.frames.iter().map(|f| f.index).collect();
+            for transfer in transfers_in_log(
+                &self.registry,
+                evm_data,
+                address,
+                topics,
+                data,
+            ) {
+                let flow = MoneyFlow::token(
+                    self.trace.money_flows.len() + 1,
+                    *address,
+                    transfer,
+                    call_path.clone(),
+                );
+                self.trace.money_flows.push(flow);
+            }
+        }
+    }
+}
+
+impl<BS: BcState> EvmInspector<BS> for TxTracer {
+    fn transaction(&mut self, tx: &TxEnv, _state: &BS) -> bool {
+        self.trace = TxTrace {
+            meta: Some(TxMeta::new(self.traces.len(), tx)),
+            ..Default::default()
+        };
+        self.storage.reset();
+        self.frames.clear();
+        self.pending_steps.clear();
+        true
+    }
+
+    fn transaction_end(
+        &mut self,
+        _tx: &TxEnv,
+        _state: &BS,
+        result: &ExecutionResult,
+    ) {
+        let mut trace = self.finish();
+        if let Some(meta) = trace.meta.as_mut() {
+            meta.set_result(result);
+        }
+        self.traces.push(trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy_dyn_abi::JsonAbiExt;
+    use alloy_json_abi::Function;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::{
+            guard::{ExecutionGuard, Interruption},
+            inspector::CombinedInspector,
+            memory::MemoryBcState,
+            state::{BcState, Database},
+            transition::TransitionSpecBuilder,
+            types::{opcode, Address, TransactTo, TxEnv, U256},
+        },
+        error::SoflError,
+    };
+    use libsofl_utils::solidity::{
+        caller::HighLevelCaller, scripting::deploy_contracts,
+    };
+
+    use crate::{
+        selectors::SelectorDb,
+        trace::{RevertedFlows, TokenKind},
+    };
+
+    use super::{TraceConfig, TxTracer};
+
+    fn deploy(state: &mut MemoryBcState) -> Address {
+        let token = deploy_contracts(
+            state,
+            "0.8.12",
+            r#"
+        contract Token {
+            event Transfer(address indexed from, address indexed to, uint256 value);
+            uint256 public count;
+            function transfer(address to, uint256 value) public {
+                count += 1;
+                emit Transfer(msg.sender, to, value);
+            }
+            function transferAndRevert(address to, uint256 value) public {
+                transfer(to, value);
+                revert("no transfer");
+            }
+        }
+        "#,
+            vec!["Token"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let code = format!(
+            r#"
+        interface Token {{
+            function transfer(address to, uint256 value) external;
+            function transferAndRevert(address to, uint256 value) external;
+        }}
+        contract B {{
+            function foo() public {{
+                Token t = Token({});
+                t.transfer(address(1), 1);
+                try t.transferAndRevert(address(2), 2) {{ }} catch {{ }}
+            }}
+        }}
+        "#,
+            token
+        );
+        deploy_contracts(state, "0.8.12", code, vec!["B"], Default::default())
+            .unwrap()
+            .remove(0)
+    }
+
+    fn trace(config: TraceConfig) -> super::TxTrace {
+        run(TxTracer::new(config))
+    }
+
+    fn run(mut tracer: TxTracer) -> super::TxTrace {
+        let mut state = MemoryBcState::fresh();
+        let contract = deploy(&mut state);
+        let input = Function::parse("foo()")
+            .unwrap()
+            .abi_encode_input(&[])
+            .unwrap();
+        HighLevelCaller::default()
+            .bypass_check()
+            .call(&mut state, contract, input.cvt(), None, &mut tracer)
+            .unwrap();
+        tracer.into_trace()
+    }
+
+    #[test]
+    fn test_trace_reverted_call() {
+        let trace = trace(TraceConfig::all());
+        assert_eq!(trace.calls.len(), 3);
+        assert_eq!(
+            trace.calls.iter().map(|c| c.parent).collect::<Vec<_>>(),
+            vec![0, 1, 1]
+        );
+        assert!(!trace.calls[0].reverted && trace.calls[0].is_success());
+        assert!(!trace.calls[1].reverted);
+        assert!(trace.calls[2].reverted && !trace.calls[2].is_success());
+        assert!(trace.calls[..2].iter().all(|c| c.revert_reason.is_none()));
+        assert_eq!(
+            trace.calls[2].revert_reason.as_deref(),
+            Some("no transfer")
+        );
+        assert!(trace.calls.iter().all(|c| !c.opcodes.is_empty()));
+        let opcodes: usize = trace
+            .calls
+            .iter()
+            .map(|c| trace.opcodes_of(c).count())
+            .sum();
+        assert_eq!(opcodes, trace.opcodes.len());
+
+        // rich steps
+        assert_eq!(trace.steps.len(), trace.opcodes.len());
+        assert!(trace.steps.iter().all(|s| s.stack.len() <= 4));
+        // steps line up with opcodes across sub-calls, and calls include their gas
+        for (o, s) in trace.opcodes.iter().zip(&trace.steps) {
+            match o.op {
+                opcode::PUSH1 => assert_eq!(s.gas_cost, 3),
+                opcode::CALL => assert!(s.gas_cost > 100),
+                _ => {}
+            }
+        }
+        let sstores: Vec<_> = trace
+            .opcodes
+            .iter()
+            .zip(&trace.steps)
+            .filter(|(o, _)| o.op == opcode::SSTORE)
+            .map(|(_, s)| s.storage.clone().unwrap())
+            .collect();
+        assert_eq!(sstores.len(), 2);
+        assert_eq!(sstores[0].old, U256::ZERO);
+        assert_eq!(sstores[0].new, U256::from(1));
+        assert_eq!(sstores[1].old, U256::from(1));
+        assert_eq!(sstores[1].new, U256::from(2));
+
+        // the transfer in the reverted call is dropped
+        assert_eq!(trace.money_flows.len(), 1);
+        assert_eq!(trace.money_flows[0].kind, TokenKind::Erc20);
+        assert_eq!(trace.money_flows[0].call_path, vec![1, 2]);
+
+        // logs and storage accesses are kept and marked
+        assert_eq!(trace.logs.len(), 2);
+        assert!(trace.logs[1].reverted);
+        assert!(trace.storage.iter().any(|s| s.write && !s.reverted));
+        assert!(trace.storage.iter().any(|s| s.write && s.reverted));
+        let writes: Vec<_> = trace
+            .storage
+            .iter()
+            .filter(|s| s.write)
+            .map(|s| (s.old, s.value, s.call_index))
+            .collect();
+        assert_eq!(
+            writes,
+            vec![
+                (U256::ZERO, U256::from(1), 2),
+                (U256::from(1), U256::from(2), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_marked_flows_only() {
+        let trace = trace(TraceConfig {
+            call_tree: false,
+            reverted_flows: RevertedFlows::Mark,
+            ..Default::default()
+        });
+        assert!(trace.calls.is_empty());
+        assert!(trace.logs.is_empty());
+        assert!(trace.storage.is_empty());
+        assert_eq!(trace.money_flows.len(), 2);
+        assert!(trace.money_flows[1].reverted);
+        assert_eq!(trace.money_flows[1].call_path, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_trace_decoded_calls() {
+        let mut selectors = SelectorDb::with_periphery_abis();
+        selectors
+            .add_signature("transferAndRevert(address,uint256)")
+            .unwrap();
+        let trace = run(TxTracer::new(TraceConfig::default())
+            .with_selectors(Arc::new(selectors)));
+        let functions: Vec<_> =
+            trace.calls.iter().map(|c| c.function()).collect();
+        assert_eq!(
+            functions,
+            vec![
+                None,
+                Some("transfer(address,uint256)"),
+                Some("transferAndRevert(address,uint256)")
+            ]
+        );
+        let transfer = trace.calls[1].decoded.as_ref().unwrap();
+        assert!(transfer
+            .inputs
+            .as_object()
+            .unwrap()
+            .values()
+            .any(|v| v == "1"));
+        // the token returns nothing instead of the bool in the standard abi
+        assert!(transfer.outputs.is_none());
+        let reverted = trace.calls[2].decoded.as_ref().unwrap();
+        assert_eq!(
+            reverted.inputs,
+            serde_json::json!([Address::with_last_byte(2).to_string(), "2"])
+        );
+    }
+
+    #[test]
+    fn test_trace_per_transaction() {
+        let mut state = MemoryBcState::fresh();
+        let contract = deploy(&mut state);
+        let input = Function::parse("foo()")
+            .unwrap()
+            .abi_encode_input(&[])
+            .unwrap();
+        let mut tx = TxEnv::default();
+        tx.caller = Address::with_last_byte(9);
+        tx.transact_to = TransactTo::Call(contract);
+        tx.data = input.into();
+        tx.gas_limit = 1_000_000;
+        let spec = TransitionSpecBuilder::default()
+            .bypass_check()
+            .append_tx_env(tx.clone())
+            .append_tx_env(tx)
+            .build();
+        let mut tracer = TxTracer::new(TraceConfig::all());
+        state.transit(spec, &mut tracer).unwrap();
+        let traces = tracer.into_traces();
+
+        assert_eq!(traces.len(), 2);
+        for (i, trace) in traces.iter().enumerate() {
+            let meta = trace.meta.as_ref().unwrap();
+            assert_eq!(meta.index, i);
+            assert_eq!(meta.caller, Address::with_last_byte(9));
+            assert_eq!(meta.to, Some(contract));
+            assert!(meta.success && meta.gas_used > 0);
+            // the log in the reverted call is not in the receipt
+            assert_eq!(meta.logs, 1);
+            assert_eq!(trace.logs.len(), 2);
+
+            // indices restart in each transaction
+            assert_eq!(trace.calls.len(), 3);
+            assert_eq!(trace.calls[0].index, 1);
+            assert_eq!(trace.money_flows[0].index, 1);
+            assert_eq!(trace.storage[0].index, 1);
+            assert_eq!(trace.logs[0].index, 1);
+            assert!(trace.opcodes_of(&trace.calls[0]).count() > 0);
+        }
+        // the counter is 1 after the first transaction, as the second increment is reverted
+        let writes = |i: usize| {
+            traces[i]
+                .storage
+                .iter()
+                .filter(|s| s.write)
+                .map(|s| (s.old, s.value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(writes(0)[0], (U256::ZERO, U256::from(1)));
+        assert_eq!(writes(1)[0], (U256::from(1), U256::from(2)));
+        assert_eq!(traces[0].opcodes.len(), traces[1].opcodes.len());
+    }
+
+    /// A contract calling itself recursively `n` times, with a loop in each frame.
+    fn deploy_deep(state: &mut MemoryBcState) -> Address {
+        deploy_contracts(
+            state,
+            "0.8.12",
+            r#"
+        contract Deep {
+            uint256 public sum;
+            function rec(uint256 n) public {
+                for (uint256 i = 0; i < 10; i++) {
+                    sum += i;
+                }
+                if (n > 0) {
+                    this.rec(n - 1);
+                }
+            }
+        }
+        "#,
+            vec!["Deep"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0)
+    }
+
+    fn rec_input(n: u64) -> Vec<u8> {
+        Function::parse("rec(uint256)")
+            .unwrap()
+            .abi_encode_input(&[U256::from(n).into()])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deep_call_opcodes_linear() {
+        let mut state = MemoryBcState::fresh();
+        let contract = deploy_deep(&mut state);
+
+        let mut run = |depth: u64| {
+            let mut tracer = TxTracer::new(TraceConfig::all());
+            HighLevelCaller::default()
+                .bypass_check()
+                .set_gas_limit(1_000_000_000)
+                .call(
+                    &mut state,
+                    contract,
+                    rec_input(depth).cvt(),
+                    None,
+                    &mut tracer,
+                )
+                .unwrap();
+            let trace = tracer.into_trace();
+
+            // every opcode is stored once, and each frame has at most two ranges:
+            // before and after its only sub-call
+            assert_eq!(trace.calls.len() as u64, depth + 1);
+            let ranges: Vec<_> =
+                trace.calls.iter().flat_map(|c| c.opcodes.clone()).collect();
+            assert!(ranges.len() <= 2 * trace.calls.len());
+            assert_eq!(
+                ranges.iter().map(|r| r.len()).sum::<usize>(),
+                trace.opcodes.len()
+            );
+            trace
+        };
+
+        // every non-leaf frame runs the same opcodes, so the trace grows
+        // linearly with the depth rather than copying the opcodes of sub-calls
+        let small = run(50);
+        let large = run(200);
+        let per_frame =
+            |trace: &super::TxTrace| trace.opcodes_of(&trace.calls[1]).count();
+        assert_eq!(per_frame(&small), per_frame(&large));
+        assert_eq!(
+            large.opcodes.len() - small.opcodes.len(),
+            150 * per_frame(&small)
+        );
+    }
+
+    #[test]
+    fn test_trace_interrupted() {
+        let mut state = MemoryBcState::fresh();
+        let contract = deploy_deep(&mut state);
+        let mut tx = TxEnv::default();
+        tx.transact_to = TransactTo::Call(contract);
+        tx.data = rec_input(20).into();
+        tx.gas_limit = 1_000_000_000;
+        let spec = TransitionSpecBuilder::default()
+            .bypass_check()
+            .append_tx_env(tx)
+            .build();
+
+        // the guard stops the recursion halfway
+        let mut guard = ExecutionGuard::new().with_max_steps(2_000);
+        let mut tracer = TxTracer::new(TraceConfig::all());
+        let mut insp = CombinedInspector::default();
+        insp.add(&mut guard);
+        insp.add(&mut tracer);
+        let err = state.transit(spec, &mut insp).unwrap_err();
+        drop(insp);
+        assert!(matches!(err, SoflError::Interrupted));
+        assert_eq!(guard.interruption, Some(Interruption::Steps));
+
+        // every step is closed and every frame popped while the frames halt
+        assert!(tracer.pending_steps.is_empty());
+        assert!(tracer.frames.is_empty());
+        let trace = tracer.into_trace();
+        assert!(trace.calls.len() > 1 && trace.calls.len() < 21);
+        assert!(trace.calls.iter().all(|c| !c.is_success()));
+        for (o, s) in trace.opcodes.iter().zip(&trace.steps) {
+            if o.op == opcode::CALL {
+                assert!(s.gas_cost > 100);
+            }
+        }
+        // the interrupted transaction is not committed
+        assert_eq!(state.storage(contract, U256::ZERO).unwrap(), U256::ZERO);
+    }
+}
@@ -1,4 +1,13 @@
+pub mod block_tracer;
 pub mod config;
+pub mod dataset;
+pub mod delta;
+pub mod detectors;
 pub mod entities;
+pub mod events;
+pub mod graph;
 pub mod inspectors;
+pub mod selectors;
 pub mod testing;
+pub mod trace;
+pub mod valuation;
@@ -0,0 +1,274 @@
+//! Decoding of call frames: function signatures from 4-byte selectors,
+//! and arguments and return values from ABIs.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Function, JsonAbi, Param};
+use alloy_primitives::{hex, Selector};
+use libsofl_core::error::SoflError;
+use libsofl_periphery::addressbook::ABI_JSONS;
+use libsofl_utils::solidity::revert::RevertDecoder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A call decoded with a function signature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedCall {
+    /// Function signature, e.g., `transfer(address,uint256)`.
+    pub function: String,
+    /// Arguments, an object by parameter names if all parameters are named, otherwise an array.
+    /// Integers are decimal strings, and bytes are hex strings.
+    pub inputs: Value,
+    /// Return values in the same form as `inputs`,
+    /// `None` if the call fails or the outputs of the function are unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Value>,
+}
+
+/// Candidate functions of selectors, and custom errors for decoding revert data.
+/// Functions from ABIs know their outputs and parameter names, while functions parsed from
+/// signatures (e.g., a 4byte directory dump) only know their input types.
+#[derive(Clone, Debug, Default)]
+pub struct SelectorDb {
+    functions: HashMap<Selector, Vec<Function>>,
+    errors: RevertDecoder,
+}
+
+impl SelectorDb {
+    /// A database without any function.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// A database with the functions in the ABIs shipped in `libsofl-periphery`.
+    pub fn with_periphery_abis() -> Self {
+        let mut db = Self::empty();
+        for (name, json) in ABI_JSONS {
+            db.add_abi_json(json)
+                .unwrap_or_else(|e| panic!("bug: invalid abi {}: {}", name, e));
+        }
+        db
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.values().map(|fs| fs.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Add a function, ignoring duplicated signatures.
+    /// Functions added earlier are tried first when decoding.
+    pub fn add_function(&mut self, function: Function) -> &mut Self {
+        let candidates = self.functions.entry(function.selector()).or_default();
+        let signature = function.signature();
+        if !candidates.iter().any(|f| f.signature() == signature) {
+            candidates.push(function);
+        }
+        self
+    }
+
+    /// Decoder of revert data with the custom errors in the added ABIs.
+    pub fn errors(&self) -> &RevertDecoder {
+        &self.errors
+    }
+
+    /// Add the functions and custom errors in the JSON ABI.
+    pub fn add_abi_json(&mut self, json: &str) -> Result<&mut Self, SoflError> {
+        let abi: JsonAbi = serde_json::from_str(json)
+            .map_err(|e| SoflError::Abi(format!("invalid json abi: {}", e)))?;
+        for function in abi.functions() {
+            self.add_function(function.clone());
+        }
+        self.errors.add_abi(&abi);
+        Ok(self)
+    }
+
+    /// Add a function by its signature, e.g., `transfer(address,uint256)`.
+    pub fn add_signature(
+        &mut self,
+        signature: &str,
+    ) -> Result<&mut Self, SoflError> {
+        let function = Function::parse(signature).map_err(|e| {
+            SoflError::Abi(format!("invalid signature {}: {}", signature, e))
+        })?;
+        Ok(self.add_function(function))
+    }
+
+    /// Add the signatures in a file, one per line, such as a dump of the 4byte directory.
+    /// The signature is taken from the word containing the first `(` to the last `)` of a line,
+    /// so lines like `0xa9059cbb,transfer(address,uint256)` are accepted.
+    /// Returns the number of signatures added; invalid lines are skipped.
+    pub fn load_signatures(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<usize, SoflError> {
+        let path = path.as_ref();
+        let io_err = |e: std::io::Error| {
+            SoflError::Custom(format!(
+                "failed to read {}: {}",
+                path.display(),
+                e
+            ))
+        };
+        let file = File::open(path).map_err(io_err)?;
+        let before = self.len();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(io_err)?;
+            if let Some(signature) = signature_in_line(&line) {
+                let _ = self.add_signature(signature);
+            }
+        }
+        Ok(self.len() - before)
+    }
+
+    /// Candidate functions of a selector.
+    pub fn functions(&self, selector: &Selector) -> &[Function] {
+        self.functions
+            .get(selector)
+            .map(|fs| fs.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Decode the calldata, and the return data if the call succeeds,
+    /// with the first candidate function accepting the calldata.
+    pub fn decode(
+        &self,
+        input: &[u8],
+        output: &[u8],
+        success: bool,
+    ) -> Option<DecodedCall> {
+        let selector = Selector::try_from(input.get(..4)?).ok()?;
+        self.functions(&selector).iter().find_map(|function| {
+            let inputs = function.abi_decode_input(&input[4..], false).ok()?;
+            let outputs = if success && !function.outputs.is_empty() {
+                function
+                    .abi_decode_output(output, false)
+                    .ok()
+                    .map(|values| to_json(&function.outputs, &values))
+            } else {
+                None
+            };
+            Some(DecodedCall {
+                function: function.signature(),
+                inputs: to_json(&function.inputs, &inputs),
+                outputs,
+            })
+        })
+    }
+}
+
+fn signature_in_line(line: &str) -> Option<&str> {
+    let open = line.find('(')?;
+    let close = line.rfind(')')?;
+    let start = line[..open]
+        .rfind(|c: char| c == ',' || c == '"' || c.is_whitespace())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    (start < open && open < close).then(|| &line[start..=close])
+}
+
+fn to_json(params: &[Param], values: &[DynSolValue]) -> Value {
+    if !params.is_empty() && params.iter().all(|p| !p.name.is_empty()) {
+        Value::Object(
+            params
+                .iter()
+                .zip(values)
+                .map(|(p, v)| (p.name.clone(), value_to_json(v)))
+                .collect::<Map<_, _>>(),
+        )
+    } else {
+        Value::Array(values.iter().map(value_to_json).collect())
+    }
+}
+
+#[allow(unreachable_patterns)]
+fn value_to_json(value: &DynSolValue) -> Value {
+    match value {
+        DynSolValue::Bool(b) => Value::Bool(*b),
+        DynSolValue::Int(i, _) => Value::String(i.to_string()),
+        DynSolValue::Uint(u, _) => Value::String(u.to_string()),
+        DynSolValue::FixedBytes(word, size) => {
+            Value::String(hex::encode_prefixed(&word[..*size]))
+        }
+        DynSolValue::Address(a) => Value::String(a.to_string()),
+        DynSolValue::Function(f) => Value::String(hex::encode_prefixed(f)),
+        DynSolValue::Bytes(b) => Value::String(hex::encode_prefixed(b)),
+        DynSolValue::String(s) => Value::String(s.clone()),
+        DynSolValue::Array(vs)
+        | DynSolValue::FixedArray(vs)
+        | DynSolValue::Tuple(vs) => {
+            Value::Array(vs.iter().map(value_to_json).collect())
+        }
+        _ => Value::String(format!("{:?}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+    use alloy_json_abi::Function;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::types::{Address, U256},
+    };
+    use serde_json::json;
+    use tempfile::NamedTempFile;
+
+    use super::SelectorDb;
+
+    #[test]
+    fn test_decode_with_periphery_abis() {
+        let db = SelectorDb::with_periphery_abis();
+        let to: Address = 0x1234.cvt();
+        let input = Function::parse("transfer(address,uint256)")
+            .unwrap()
+            .abi_encode_input(&[to.into(), U256::from(42).into()])
+            .unwrap();
+        let output = DynSolValue::Bool(true).abi_encode();
+        let decoded = db.decode(&input, &output, true).unwrap();
+        assert_eq!(decoded.function, "transfer(address,uint256)");
+        assert_eq!(decoded.inputs.as_object().unwrap().len(), 2);
+        assert!(decoded
+            .inputs
+            .as_object()
+            .unwrap()
+            .values()
+            .any(|v| v == &json!("42")));
+        assert_eq!(decoded.outputs.unwrap(), json!([true]));
+
+        // failed calls have no outputs
+        let decoded = db.decode(&input, &[], false).unwrap();
+        assert!(decoded.outputs.is_none());
+    }
+
+    #[test]
+    fn test_load_signatures() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "0xdeadbeef,withdrawAll(uint8[],bytes)").unwrap();
+        writeln!(file, "not a signature").unwrap();
+        writeln!(file, "exploit(address)").unwrap();
+        file.flush().unwrap();
+
+        let mut db = SelectorDb::empty();
+        assert_eq!(db.load_signatures(file.path()).unwrap(), 2);
+
+        let input = Function::parse("exploit(address)")
+            .unwrap()
+            .abi_encode_input(&[Address::ZERO.into()])
+            .unwrap();
+        let decoded = db.decode(&input, &[], true).unwrap();
+        assert_eq!(decoded.function, "exploit(address)");
+        assert_eq!(decoded.inputs, json!([Address::ZERO.to_string()]));
+        assert!(db.decode(&[0, 0, 0, 0], &[], true).is_none());
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(test)]
+pub(crate) mod fixtures;
+
 use sea_orm::{
     ConnectionTrait, Database, DatabaseConnection, DbBackend, Schema,
 };
@@ -0,0 +1,213 @@
+//! Contracts deployed and called by the unit tests.
+//! Each fixture deploys the contract `Main` of its code and calls `Main.run()`.
+
+use alloy_dyn_abi::JsonAbiExt;
+use alloy_json_abi::Function;
+use libsofl_core::{
+    conversion::ConvertTo,
+    engine::{
+        inspector::EvmInspector,
+        memory::{EmptyMemoryBcState, MemoryBcState},
+        types::{Address, Bytes, StateChange, U256},
+    },
+};
+use libsofl_utils::solidity::{
+    caller::HighLevelCaller,
+    scripting::{deploy_contracts, SolScriptConfig},
+};
+
+use crate::{
+    inspectors::tx_tracer::{TraceConfig, TxTracer},
+    trace::TxTrace,
+};
+
+/// A token emitting `Transfer` events without keeping balances.
+const TOKEN: &str = r#"
+contract Token {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    function transfer(address to, uint256 value) public returns (bool) {
+        emit Transfer(msg.sender, to, value);
+        return true;
+    }
+    function transferFrom(address from, address to, uint256 value) public returns (bool) {
+        emit Transfer(from, to, value);
+        return true;
+    }
+}
+"#;
+
+/// An ERC20 asset and an ERC4626 vault minting one share per two assets.
+/// `Main` deploys both and deposits 100 assets into the vault.
+const VAULT: &str = r#"
+contract Asset {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    mapping(address => uint256) public balanceOf;
+    mapping(address => mapping(address => uint256)) public allowance;
+    function mint(address to, uint256 amount) public {
+        balanceOf[to] += amount;
+        emit Transfer(address(0), to, amount);
+    }
+    function approve(address spender, uint256 amount) public returns (bool) {
+        allowance[msg.sender][spender] = amount;
+        return true;
+    }
+    function transferFrom(address from, address to, uint256 amount) public returns (bool) {
+        allowance[from][msg.sender] -= amount;
+        balanceOf[from] -= amount;
+        balanceOf[to] += amount;
+        emit Transfer(from, to, amount);
+        return true;
+    }
+}
+contract Vault {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Deposit(address indexed sender, address indexed owner, uint256 assets, uint256 shares);
+    Asset public asset;
+    mapping(address => uint256) public balanceOf;
+    constructor(Asset _asset) {
+        asset = _asset;
+    }
+    function deposit(uint256 assets, address receiver) public returns (uint256 shares) {
+        shares = assets / 2;
+        asset.transferFrom(msg.sender, address(this), assets);
+        balanceOf[receiver] += shares;
+        emit Transfer(address(0), receiver, shares);
+        emit Deposit(msg.sender, receiver, assets, shares);
+    }
+}
+contract Main {
+    Asset public asset;
+    Vault public vault;
+    constructor() {
+        asset = new Asset();
+        vault = new Vault(asset);
+    }
+    function run() public {
+        asset.mint(address(this), 100);
+        asset.approve(address(vault), 100);
+        vault.deposit(100, address(this));
+    }
+}
+"#;
+
+/// A transaction with nested calls, a contract creation, logs,
+/// a caught revert and a selfdestruct.
+const NESTED: &str = r#"
+contract Child {
+    event Hello(uint256 x);
+    function hello(uint256 x) public returns (uint256) {
+        emit Hello(x);
+        return x + 1;
+    }
+    function fail() public pure {
+        revert("child failed");
+    }
+    function destroy() public {
+        selfdestruct(payable(msg.sender));
+    }
+}
+contract Main {
+    event Done(address child);
+    uint256 public last;
+    function run() public payable {
+        Child child = new Child();
+        last = child.hello(41);
+        try child.fail() { } catch { }
+        child.destroy();
+        emit Done(address(child));
+    }
+}
+"#;
+
+/// Deploy `Main` of the code, funding it with ether via its payable constructor.
+pub fn deploy_main(code: &str, prefund: U256) -> (EmptyMemoryBcState, Address) {
+    let mut state = MemoryBcState::fresh();
+    let main = deploy_contracts(
+        &mut state,
+        "0.8.12",
+        code,
+        vec!["Main"],
+        SolScriptConfig {
+            prefund,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .remove(0);
+    (state, main)
+}
+
+fn caller() -> HighLevelCaller {
+    HighLevelCaller::default()
+        .bypass_check()
+        .set_gas_limit(10_000_000)
+}
+
+fn run_input() -> Bytes {
+    Function::parse("run()")
+        .unwrap()
+        .abi_encode_input(&[])
+        .unwrap()
+        .cvt()
+}
+
+/// Deploy `Main` of the code and call `Main.run()` with the inspector.
+/// Returns the state after the call.
+pub fn deploy_and_run<I>(
+    code: &str,
+    prefund: U256,
+    inspector: &mut I,
+) -> EmptyMemoryBcState
+where
+    I: for<'a> EvmInspector<&'a mut EmptyMemoryBcState>,
+{
+    let (mut state, main) = deploy_main(code, prefund);
+    caller()
+        .call(&mut state, main, run_input(), None, inspector)
+        .unwrap();
+    state
+}
+
+/// Trace `Main.run()` of the code, which can use `Token`.
+pub fn trace_with_token(code: &str) -> TxTrace {
+    trace_with_token_funded(code, U256::ZERO)
+}
+
+/// Same as `trace_with_token`, funding `Main` with ether.
+pub fn trace_with_token_funded(code: &str, prefund: U256) -> TxTrace {
+    let mut tracer = TxTracer::new(TraceConfig::default());
+    deploy_and_run(&format!("{}{}", TOKEN, code), prefund, &mut tracer);
+    tracer.into_trace()
+}
+
+/// Run `Main.run()` of `NESTED` with the inspector.
+pub fn run_nested<I>(inspector: &mut I)
+where
+    I: for<'a> EvmInspector<&'a mut EmptyMemoryBcState>,
+{
+    deploy_and_run(NESTED, U256::ZERO, inspector);
+}
+
+/// A traced deposit into the vault, not yet applied to `state`.
+pub struct VaultDeposit {
+    /// The state before the deposit.
+    pub state: EmptyMemoryBcState,
+    pub changes: Vec<StateChange>,
+    pub trace: TxTrace,
+    /// The depositor, i.e., `Main`.
+    pub main: Address,
+}
+
+pub fn vault_deposit() -> VaultDeposit {
+    let (mut state, main) = deploy_main(VAULT, U256::ZERO);
+    let mut tracer = TxTracer::new(TraceConfig::default());
+    let (_, change) = caller()
+        .simulate_call(&mut state, main, run_input(), None, &mut tracer)
+        .unwrap();
+    VaultDeposit {
+        state,
+        changes: vec![change],
+        trace: tracer.into_trace(),
+        main,
+    }
+}
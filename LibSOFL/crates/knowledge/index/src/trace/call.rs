@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use libsofl_core::engine::types::{
+    Address, Bytes, CreateScheme, InstructionResult, U256,
+};
+use revm::interpreter::CallScheme;
+use serde::{Deserialize, Serialize};
+
+use crate::selectors::DecodedCall;
+
+/// The kind of a call frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+    SelfDestruct,
+}
+
+impl CallKind {
+    /// Name of the kind, same as its serialized form.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CallKind::Call => "CALL",
+            CallKind::CallCode => "CALLCODE",
+            CallKind::DelegateCall => "DELEGATECALL",
+            CallKind::StaticCall => "STATICCALL",
+            CallKind::Create => "CREATE",
+            CallKind::Create2 => "CREATE2",
+            CallKind::SelfDestruct => "SELFDESTRUCT",
+        }
+    }
+
+    pub fn is_create(&self) -> bool {
+        matches!(self, CallKind::Create | CallKind::Create2)
+    }
+}
+
+impl From<CallScheme> for CallKind {
+    fn from(scheme: CallScheme) -> Self {
+        match scheme {
+            CallScheme::Call => CallKind::Call,
+            CallScheme::CallCode => CallKind::CallCode,
+            CallScheme::DelegateCall => CallKind::DelegateCall,
+            CallScheme::StaticCall => CallKind::StaticCall,
+        }
+    }
+}
+
+impl From<CreateScheme> for CallKind {
+    fn from(scheme: CreateScheme) -> Self {
+        match scheme {
+            CreateScheme::Create => CallKind::Create,
+            CreateScheme::Create2 { .. } => CallKind::Create2,
+        }
+    }
+}
+
+/// A call frame (message call, contract creation or selfdestruct) in a transaction.
+///
+/// The meaning of `from` and `to` depends on `kind`:
+/// - calls: the caller and the callee (whose storage is used).
+/// - creations: the creator and the created contract.
+/// - selfdestruct: the destructed contract and the beneficiary.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallTrace {
+    /// Index of the call frame in the transaction, starting from 1.
+    pub index: usize,
+    /// Index of the parent call frame, 0 for the outermost one.
+    #[serde(default)]
+    pub parent: usize,
+    pub kind: CallKind,
+    pub from: Address,
+    pub to: Address,
+    /// Calldata for calls, init code for creations.
+    pub input: Bytes,
+    /// Return data for calls, runtime code for creations.
+    pub output: Bytes,
+    pub value: U256,
+    /// How the call frame ends.
+    pub result: InstructionResult,
+    pub gas_used: u64,
+    /// Whether the effects of the call frame are rolled back,
+    /// either by itself or by one of its ancestors.
+    pub reverted: bool,
+    /// Ranges of `TxTrace::opcodes` executed directly in this call frame,
+    /// which are interleaved with the opcodes of its sub-calls.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opcodes: Vec<Range<usize>>,
+    /// Function, arguments and return values of a message call,
+    /// if the tracer is given a selector database and the calldata is decodable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<DecodedCall>,
+    /// Decoded revert data of a call frame ending with a revert,
+    /// `None` if the revert data is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+}
+
+impl CallTrace {
+    pub fn new(
+        index: usize,
+        kind: CallKind,
+        from: Address,
+        to: Address,
+        input: Bytes,
+        value: U256,
+    ) -> Self {
+        Self {
+            index,
+            parent: 0,
+            kind,
+            from,
+            to,
+            input,
+            output: Bytes::new(),
+            value,
+            result: InstructionResult::Continue,
+            gas_used: 0,
+            reverted: false,
+            opcodes: Vec::new(),
+            decoded: None,
+            revert_reason: None,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Signature of the decoded function.
+    pub fn function(&self) -> Option<&str> {
+        self.decoded.as_ref().map(|d| d.function.as_str())
+    }
+}
@@ -0,0 +1,16 @@
+use libsofl_core::engine::types::{Address, Bytes, B256};
+use serde::{Deserialize, Serialize};
+
+/// An event log emitted in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Index of the log in the transaction, starting from 1.
+    pub index: usize,
+    /// Index of the call frame emitting the log.
+    pub call_index: usize,
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    /// Whether the log is emitted inside a reverted call frame.
+    pub reverted: bool,
+}
@@ -0,0 +1,15 @@
+pub mod call;
+pub mod log;
+pub mod money_flow;
+pub mod opcode;
+pub mod step;
+pub mod storage;
+pub mod tx;
+
+pub use call::{CallKind, CallTrace};
+pub use log::LogRecord;
+pub use money_flow::{Asset, FlowAction, MoneyFlow, RevertedFlows, TokenKind};
+pub use opcode::Opcode;
+pub use step::{SlotChange, StepDetail, StepRecord};
+pub use storage::StorageAccess;
+pub use tx::{TxMeta, TxTrace};
@@ -0,0 +1,224 @@
+use std::fmt;
+
+use libsofl_core::engine::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::events::TokenTransfer;
+
+/// The kind of asset moved by a money flow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenKind {
+    #[serde(rename = "ETH")]
+    Eth,
+    #[serde(rename = "ERC20")]
+    Erc20,
+    #[serde(rename = "ERC721")]
+    Erc721,
+    #[serde(rename = "ERC777")]
+    Erc777,
+    #[serde(rename = "ERC1155")]
+    Erc1155,
+}
+
+impl TokenKind {
+    /// Name of the kind, same as its serialized form.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Eth => "ETH",
+            TokenKind::Erc20 => "ERC20",
+            TokenKind::Erc721 => "ERC721",
+            TokenKind::Erc777 => "ERC777",
+            TokenKind::Erc1155 => "ERC1155",
+        }
+    }
+}
+
+/// How a money flow changes the supply of the asset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FlowAction {
+    Transfer,
+    Mint,
+    Burn,
+}
+
+impl FlowAction {
+    /// Name of the action, same as its serialized form.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowAction::Transfer => "TRANSFER",
+            FlowAction::Mint => "MINT",
+            FlowAction::Burn => "BURN",
+        }
+    }
+}
+
+/// An asset moved by money flows. Each token id of ERC721 and ERC1155 tokens is an asset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Asset {
+    pub kind: TokenKind,
+    /// The token contract, `None` for ether.
+    pub token: Option<Address>,
+    pub token_id: Option<U256>,
+}
+
+impl Asset {
+    pub const ETH: Asset = Asset {
+        kind: TokenKind::Eth,
+        token: None,
+        token_id: None,
+    };
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token {
+            Some(token) => write!(f, "{}", token)?,
+            None => f.write_str(self.kind.name())?,
+        }
+        match self.token_id {
+            Some(id) => write!(f, "#{}", id),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What to do with the money flows inside reverted call frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RevertedFlows {
+    /// Only keep the committed money flows.
+    #[default]
+    Drop,
+    /// Keep all money flows and set their `reverted` flag.
+    Mark,
+}
+
+impl RevertedFlows {
+    /// Handle the money flows from `start` on, which happen inside a reverted call frame.
+    pub fn apply(self, moneys: &mut Vec<MoneyFlow>, start: usize) {
+        match self {
+            RevertedFlows::Drop => moneys.truncate(start),
+            RevertedFlows::Mark => moneys[start..]
+                .iter_mut()
+                .for_each(|flow| flow.reverted = true),
+        }
+    }
+}
+
+/// An asset movement in a transaction, either a native ether transfer or a token transfer event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoneyFlow {
+    /// Index of the money flow in the transaction, starting from 1.
+    pub index: usize,
+    pub from: Address,
+    pub to: Address,
+    pub kind: TokenKind,
+    pub action: FlowAction,
+    /// The event carrying the flow, `None` for ether.
+    pub event: Option<String>,
+    /// The token contract, `None` for ether.
+    pub token: Option<Address>,
+    /// Amount without decimals. Always 1 for ERC721 tokens.
+    pub amount: U256,
+    /// Value of the amount in wei, if the asset is priced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// Token id of ERC721 and ERC1155 tokens.
+    pub token_id: Option<U256>,
+    /// Indices of the call frames on the call stack when the flow happens.
+    pub call_path: Vec<usize>,
+    /// Whether the flow happens inside a reverted call frame.
+    pub reverted: bool,
+}
+
+impl MoneyFlow {
+    pub fn ether(
+        index: usize,
+        from: Address,
+        to: Address,
+        amount: U256,
+        call_path: Vec<usize>,
+    ) -> Self {
+        Self {
+            index,
+            from,
+            to,
+            kind: TokenKind::Eth,
+            action: FlowAction::Transfer,
+            event: None,
+            token: None,
+            amount,
+            value: None,
+            token_id: None,
+            call_path,
+            reverted: false,
+        }
+    }
+
+    pub fn token(
+        index: usize,
+        token: Address,
+        transfer: TokenTransfer,
+        call_path: Vec<usize>,
+    ) -> Self {
+        Self {
+            index,
+            from: transfer.from,
+            to: transfer.to,
+            kind: transfer.kind,
+            action: transfer.action,
+            event: Some(transfer.event),
+            token: Some(token),
+            amount: transfer.amount,
+            value: None,
+            token_id: transfer.token_id,
+            call_path,
+            reverted: false,
+        }
+    }
+
+    pub fn asset(&self) -> Asset {
+        Asset {
+            kind: self.kind,
+            token: self.token,
+            token_id: self.token_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{conversion::ConvertTo, engine::types::U256};
+
+    use super::{MoneyFlow, RevertedFlows};
+
+    fn flows() -> Vec<MoneyFlow> {
+        (1..=3)
+            .map(|i| {
+                MoneyFlow::ether(
+                    i,
+                    0x1.cvt(),
+                    0x2.cvt(),
+                    U256::from(i),
+                    vec![1, i],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reverted_flows() {
+        let mut moneys = flows();
+        RevertedFlows::Drop.apply(&mut moneys, 1);
+        assert_eq!(moneys.len(), 1);
+        assert!(!moneys[0].reverted);
+
+        let mut moneys = flows();
+        RevertedFlows::Mark.apply(&mut moneys, 1);
+        assert_eq!(moneys.len(), 3);
+        assert_eq!(
+            moneys.iter().map(|m| m.reverted).collect::<Vec<_>>(),
+            vec![false, true, true]
+        );
+    }
+}
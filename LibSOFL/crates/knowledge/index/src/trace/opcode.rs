@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+use libsofl_core::engine::types::opcode::OPCODE_JUMPMAP;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An executed opcode.
+/// Opcodes of a transaction are stored once in `TxTrace::opcodes`,
+/// and the mnemonic is only rendered when serializing.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    pub op: u8,
+    pub pc: u32,
+    pub gas_remaining: u64,
+    /// Call depth, starting from 1.
+    pub depth: u32,
+}
+
+impl Opcode {
+    /// Opcode mnemonic, e.g., `SLOAD`, or the hex byte if the opcode is undefined.
+    pub fn mnemonic(&self) -> Cow<'static, str> {
+        match OPCODE_JUMPMAP[self.op as usize] {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("0x{:02x}", self.op)),
+        }
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> Option<u8> {
+        match mnemonic.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok(),
+            None => OPCODE_JUMPMAP
+                .iter()
+                .position(|name| *name == Some(mnemonic))
+                .map(|op| op as u8),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpcodeRepr<'a> {
+    #[serde(borrow)]
+    op: Cow<'a, str>,
+    pc: u32,
+    gas_remaining: u64,
+    depth: u32,
+}
+
+impl Serialize for Opcode {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        OpcodeRepr {
+            op: self.mnemonic(),
+            pc: self.pc,
+            gas_remaining: self.gas_remaining,
+            depth: self.depth,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Opcode {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let repr = OpcodeRepr::deserialize(deserializer)?;
+        let op = Self::from_mnemonic(&repr.op).ok_or_else(|| {
+            de::Error::custom(format!("unknown opcode {}", repr.op))
+        })?;
+        Ok(Self {
+            op,
+            pc: repr.pc,
+            gas_remaining: repr.gas_remaining,
+            depth: repr.depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::engine::types::opcode;
+
+    use super::Opcode;
+
+    #[test]
+    fn test_opcode_serde() {
+        for op in [opcode::SLOAD, 0x0c] {
+            let opcode = Opcode {
+                op,
+                pc: 10,
+                gas_remaining: 100,
+                depth: 2,
+            };
+            let json = serde_json::to_string(&opcode).unwrap();
+            assert_eq!(serde_json::from_str::<Opcode>(&json).unwrap(), opcode);
+        }
+        let sload = Opcode {
+            op: opcode::SLOAD,
+            ..Default::default()
+        };
+        assert_eq!(sload.mnemonic(), "SLOAD");
+    }
+}
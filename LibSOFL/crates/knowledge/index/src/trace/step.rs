@@ -0,0 +1,34 @@
+use libsofl_core::engine::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// How much detail is recorded for each executed opcode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StepDetail {
+    /// Only `Opcode` records.
+    #[default]
+    Basic,
+    /// A `StepRecord` is also recorded for each opcode,
+    /// with the given number of words from the top of the stack.
+    Rich { stack_top: usize },
+}
+
+/// Details of an executed opcode, in addition to its `Opcode` record.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub gas_cost: u64,
+    /// Top words of the stack before the opcode is executed, the topmost first.
+    pub stack: Vec<U256>,
+    /// Memory size in bytes before the opcode is executed.
+    pub memory_size: usize,
+    /// The accessed slot of SLOAD and SSTORE.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<SlotChange>,
+}
+
+/// A storage slot and its value before and after an SLOAD or SSTORE.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotChange {
+    pub slot: U256,
+    pub old: U256,
+    pub new: U256,
+}
@@ -0,0 +1,22 @@
+use libsofl_core::engine::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+/// A storage slot read (`SLOAD`) or written (`SSTORE`) in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageAccess {
+    /// Index of the storage access in the transaction, starting from 1.
+    pub index: usize,
+    /// Index of the call frame doing the access.
+    pub call_index: usize,
+    /// The contract whose storage is accessed.
+    pub address: Address,
+    pub slot: U256,
+    /// Value of the slot before the access, same as `value` for reads.
+    #[serde(default)]
+    pub old: U256,
+    /// The value read or written.
+    pub value: U256,
+    pub write: bool,
+    /// Whether the access happens inside a reverted call frame.
+    pub reverted: bool,
+}
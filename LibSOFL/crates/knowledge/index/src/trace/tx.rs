@@ -0,0 +1,113 @@
+use libsofl_core::engine::{
+    state_diff::StateDiff,
+    types::{Address, ExecutionResult, TransactTo, TxEnv, TxHash, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    CallTrace, LogRecord, MoneyFlow, Opcode, StepRecord, StorageAccess,
+};
+
+/// Transaction-level metadata of a trace.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxMeta {
+    /// Position of the transaction among those inspected by the tracer, starting from 0.
+    pub index: usize,
+    /// Hash of the transaction, set by the caller since `TxEnv` does not carry it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<TxHash>,
+    /// Block of the transaction, set by the caller since `TxEnv` does not carry it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+    pub caller: Address,
+    /// The callee, `None` for contract creations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    pub value: U256,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub success: bool,
+    /// Number of logs emitted, zero if the transaction fails.
+    pub logs: usize,
+}
+
+impl TxMeta {
+    /// Metadata of a transaction before it is executed.
+    pub fn new(index: usize, tx: &TxEnv) -> Self {
+        Self {
+            index,
+            hash: None,
+            block: None,
+            caller: tx.caller,
+            to: match tx.transact_to {
+                TransactTo::Call(to) => Some(to),
+                TransactTo::Create(_) => None,
+            },
+            value: tx.value,
+            gas_limit: tx.gas_limit,
+            gas_used: 0,
+            success: false,
+            logs: 0,
+        }
+    }
+
+    /// Fill in the outcome of the transaction.
+    pub fn set_result(&mut self, result: &ExecutionResult) {
+        self.gas_used = result.gas_used();
+        self.success = result.is_success();
+        self.logs = match result {
+            ExecutionResult::Success { logs, .. } => logs.len(),
+            _ => 0,
+        };
+    }
+}
+
+/// Everything traced in a transaction. Parts that are not traced are left empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxTrace {
+    /// Set if the trace is segmented by the transaction hooks of `EvmInspector`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<TxMeta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub money_flows: Vec<MoneyFlow>,
+    /// Call frames in the order they start.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallTrace>,
+    /// All executed opcodes in order, referred to by the opcode ranges of call frames.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opcodes: Vec<Opcode>,
+    /// Details of each opcode, aligned with `opcodes`, if rich steps are traced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub storage: Vec<StorageAccess>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogRecord>,
+    /// State changed by the transaction, which is not computed by the tracer
+    /// but from the state changes of the transaction (see `StateDiff::new`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<StateDiff>,
+}
+
+impl TxTrace {
+    /// Opcodes executed directly in the call frame.
+    pub fn opcodes_of<'a>(
+        &'a self,
+        call: &'a CallTrace,
+    ) -> impl Iterator<Item = &'a Opcode> + 'a {
+        call.opcodes
+            .iter()
+            .flat_map(move |range| self.opcodes[range.clone()].iter())
+    }
+
+    /// Index of the call frame executing each opcode.
+    pub fn opcode_call_indices(&self) -> Vec<usize> {
+        let mut indices = vec![0; self.opcodes.len()];
+        for call in &self.calls {
+            for range in &call.opcodes {
+                indices[range.clone()].fill(call.index);
+            }
+        }
+        indices
+    }
+}
@@ -0,0 +1,232 @@
+//! Ether-denominated values of money flows, priced by the Uniswap V2/V3 pools in the pre-transaction state.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use libsofl_core::{
+    blockchain::tx_position::TxPosition,
+    engine::{
+        state::BcState,
+        types::{Address, Uint, U256},
+    },
+};
+use libsofl_periphery::cheatcodes::CheatCodes;
+use libsofl_utils::log::debug;
+
+use crate::{
+    delta::{signed_diff, AssetDeltas},
+    trace::{Asset, MoneyFlow, TokenKind},
+};
+
+type U512 = Uint<512, 8>;
+
+/// Price of a fungible token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPrice {
+    /// Wei per whole token, i.e., `10^decimals` units.
+    pub price: U256,
+    pub decimals: u8,
+}
+
+impl TokenPrice {
+    /// Value in wei of an amount without decimals, `None` on overflow.
+    pub fn value_of(&self, amount: U256) -> Option<U256> {
+        let scale = U512::from(10).pow(U512::from(self.decimals));
+        U256::checked_from(U512::from(amount) * U512::from(self.price) / scale)
+    }
+}
+
+/// Values assets in ether. Prices are cached per transaction, since each transaction
+/// moves the pools pricing the next one, and the cache is shared by clones,
+/// so that the flows and deltas of a transaction traced in parallel query each token only once.
+#[derive(Clone, Debug, Default)]
+pub struct Valuator {
+    prices: Arc<Mutex<HashMap<(TxPosition, Address), Option<TokenPrice>>>>,
+}
+
+impl Valuator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price of an ERC20 token at the state, `None` if it has no liquidity or no decimals.
+    /// `state` should be the state before the transaction at `position`.
+    pub fn price<S: BcState>(
+        &self,
+        state: &mut S,
+        position: TxPosition,
+        token: Address,
+    ) -> Option<TokenPrice>
+    where
+        S::Error: Debug,
+    {
+        let key = (position, token);
+        if let Some(price) = self.prices.lock().unwrap().get(&key) {
+            return *price;
+        }
+        let mut cheatcodes = CheatCodes::new();
+        let price = cheatcodes
+            .get_erc20_decimals(state, token)
+            .and_then(|decimals| {
+                let price = cheatcodes.get_price_in_ether(state, token)?;
+                Ok(TokenPrice {
+                    price,
+                    decimals: decimals.saturating_to(),
+                })
+            })
+            .map_err(|e| {
+                debug!(token = %token, %position, err = %e, "failed to price token");
+            })
+            .ok();
+        self.prices.lock().unwrap().insert(key, price);
+        price
+    }
+
+    /// Value of an amount of the asset in wei.
+    /// Only ether, ERC20 and ERC777 tokens are priced.
+    pub fn value<S: BcState>(
+        &self,
+        state: &mut S,
+        position: TxPosition,
+        asset: &Asset,
+        amount: U256,
+    ) -> Option<U256>
+    where
+        S::Error: Debug,
+    {
+        match (asset.kind, asset.token) {
+            (TokenKind::Eth, _) => Some(amount),
+            (TokenKind::Erc20 | TokenKind::Erc777, Some(token)) => {
+                self.price(state, position, token)?.value_of(amount)
+            }
+            _ => None,
+        }
+    }
+
+    /// Set the value of each money flow.
+    /// `state` should be the state before the transaction at `position`.
+    pub fn annotate_flows<S: BcState>(
+        &self,
+        state: &mut S,
+        position: TxPosition,
+        flows: &mut [MoneyFlow],
+    ) where
+        S::Error: Debug,
+    {
+        for flow in flows {
+            flow.value =
+                self.value(state, position, &flow.asset(), flow.amount);
+        }
+    }
+
+    /// Set the value of the net change of each asset of each address,
+    /// which also gives the profit of each address by `AssetDeltas::profits`.
+    /// `state` should be the state before the transaction at `position`.
+    pub fn annotate_deltas<S: BcState>(
+        &self,
+        state: &mut S,
+        position: TxPosition,
+        deltas: &mut AssetDeltas,
+    ) where
+        S::Error: Debug,
+    {
+        for delta in &mut deltas.deltas {
+            let received =
+                self.value(state, position, &delta.asset, delta.received);
+            let sent = self.value(state, position, &delta.asset, delta.sent);
+            delta.value = received.zip(sent).map(|(r, s)| signed_diff(r, s));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsofl_core::{
+        blockchain::{provider::BcStateProvider, tx_position::TxPosition},
+        conversion::ConvertTo,
+        engine::{memory::MemoryBcState, types::U256},
+    };
+    use libsofl_periphery::{addressbook::ADDRESS_BOOK, types::Chain};
+
+    use crate::{
+        events::TokenTransfer,
+        testing::get_bc_provider,
+        trace::{MoneyFlow, TokenKind},
+    };
+
+    use super::{TokenPrice, Valuator};
+
+    #[test]
+    fn test_token_value() {
+        let ether = U256::from(10).pow(U256::from(18));
+        // 1 token with 6 decimals is worth 0.5 ether
+        let price = TokenPrice {
+            price: ether / U256::from(2),
+            decimals: 6,
+        };
+        assert_eq!(
+            price.value_of(U256::from(3_000_000)),
+            Some(ether * U256::from(3) / U256::from(2))
+        );
+        assert_eq!(price.value_of(U256::MAX), None);
+    }
+
+    #[test]
+    fn test_annotate_ether_flow() {
+        let mut state = MemoryBcState::fresh();
+        let mut flows = vec![MoneyFlow::ether(
+            1,
+            0x1.cvt(),
+            0x2.cvt(),
+            U256::from(7),
+            vec![1],
+        )];
+        Valuator::new().annotate_flows(
+            &mut state,
+            TxPosition::new(1, 0),
+            &mut flows,
+        );
+        assert_eq!(flows[0].value, Some(U256::from(7)));
+    }
+
+    #[test]
+    fn test_annotate_erc20_flows() {
+        let bp = get_bc_provider();
+        let position = TxPosition::new(17000001, 0);
+        let mut state = bp.bc_state_at(position).unwrap();
+        let ether = U256::from(10).pow(U256::from(18));
+        let weth = ADDRESS_BOOK.weth.must_on_chain(Chain::Mainnet);
+        let usdc = ADDRESS_BOOK.usdc.must_on_chain(Chain::Mainnet);
+        let flow = |index: usize, token, amount| {
+            let transfer = TokenTransfer::new(
+                "Transfer",
+                TokenKind::Erc20,
+                0x1.cvt(),
+                0x2.cvt(),
+                amount,
+                None,
+            );
+            MoneyFlow::token(index, token, transfer, vec![1])
+        };
+        // 2 weth, and 1000 usdc with 6 decimals
+        let mut flows = vec![
+            flow(1, weth, ether * U256::from(2)),
+            flow(2, usdc, U256::from(1_000_000_000)),
+        ];
+        let valuator = Valuator::new();
+        valuator.annotate_flows(&mut state, position, &mut flows);
+        assert_eq!(flows[0].value, Some(ether * U256::from(2)));
+        // 1000 usdc is worth less than 1 ether but more than 0.1 ether at the block
+        let usdc_value = flows[1].value.unwrap();
+        assert!(usdc_value < ether && usdc_value > ether / U256::from(10));
+
+        // the next transaction in the block is priced again
+        let next = TxPosition::new(17000001, 1);
+        assert_eq!(valuator.prices.lock().unwrap().len(), 2);
+        valuator.price(&mut state, next, usdc);
+        assert_eq!(valuator.prices.lock().unwrap().len(), 3);
+    }
+}
@@ -171,6 +171,73 @@ sol!(UniswapV2PairABI, "abi/uniswap_v2_pair.abi.json");
 sol!(UniswapV2Router02ABI, "abi/uniswap_v2_router02.abi.json");
 sol!(UniswapV3PoolABI, "abi/uniswap_v3_pool.abi.json");
 
+/// JSON of all ABIs shipped in `abi`, keyed by the file name without `.abi.json`.
+pub const ABI_JSONS: &[(&str, &str)] = &[
+    (
+        "aave_atoken_v2",
+        include_str!("../abi/aave_atoken_v2.abi.json"),
+    ),
+    (
+        "aave_lending_pool_v2",
+        include_str!("../abi/aave_lending_pool_v2.abi.json"),
+    ),
+    (
+        "curve_crypto_pool",
+        include_str!("../abi/curve_crypto_pool.abi.json"),
+    ),
+    (
+        "curve_crypto_registry",
+        include_str!("../abi/curve_crypto_registry.abi.json"),
+    ),
+    (
+        "curve_exchange",
+        include_str!("../abi/curve_exchange.abi.json"),
+    ),
+    ("curve_pool", include_str!("../abi/curve_pool.abi.json")),
+    (
+        "curve_registry",
+        include_str!("../abi/curve_registry.abi.json"),
+    ),
+    (
+        "curve_y_vault",
+        include_str!("../abi/curve_y_vault.abi.json"),
+    ),
+    ("erc1155", include_str!("../abi/erc1155.abi.json")),
+    ("erc20", include_str!("../abi/erc20.abi.json")),
+    ("erc4626", include_str!("../abi/erc4626.abi.json")),
+    ("erc721", include_str!("../abi/erc721.abi.json")),
+    ("erc777", include_str!("../abi/erc777.abi.json")),
+    (
+        "inverse_lending_comptroller",
+        include_str!("../abi/inverse_lending_comptroller.abi.json"),
+    ),
+    (
+        "inverse_lending_pool",
+        include_str!("../abi/inverse_lending_pool.abi.json"),
+    ),
+    (
+        "uniswap_v2_factory",
+        include_str!("../abi/uniswap_v2_factory.abi.json"),
+    ),
+    (
+        "uniswap_v2_pair",
+        include_str!("../abi/uniswap_v2_pair.abi.json"),
+    ),
+    (
+        "uniswap_v2_router02",
+        include_str!("../abi/uniswap_v2_router02.abi.json"),
+    ),
+    (
+        "uniswap_v3_factory",
+        include_str!("../abi/uniswap_v3_factory.abi.json"),
+    ),
+    (
+        "uniswap_v3_pool",
+        include_str!("../abi/uniswap_v3_pool.abi.json"),
+    ),
+    ("weth", include_str!("../abi/weth.abi.json")),
+];
+
 #[cfg(test)]
 mod tests {
 
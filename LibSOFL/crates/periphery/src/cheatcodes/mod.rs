@@ -11,15 +11,15 @@ use alloy_json_abi::Function;
 use libsofl_core::{
     conversion::ConvertTo,
     engine::{
+        inspector::no_inspector,
         state::BcState,
+        storage_access::StorageAccessInspector,
         types::{Address, Bytecode, Bytes, B256, U256},
     },
     error::SoflError,
 };
 
-mod inspector;
 use alloy_sol_types::SolType;
-use inspector::CheatcodeInspector;
 
 mod contract_type;
 mod erc20;
@@ -32,8 +32,8 @@ enum SlotQueryResult {
 }
 
 pub struct CheatCodes {
-    // runtime env
-    inspector: CheatcodeInspector,
+    // storage accesses of slot queries
+    inspector: StorageAccessInspector,
 
     // slot info: (codehash, calldata) -> slot_state
     slots: BTreeMap<(B256, Bytes), SlotQueryResult>,
@@ -61,7 +61,7 @@ impl CheatCodes {
     pub fn new() -> Self {
         Self {
             caller: HighLevelCaller::default().bypass_check(),
-            inspector: CheatcodeInspector::default(),
+            inspector: StorageAccessInspector::default(),
             slots: BTreeMap::new(),
             // abi_parser: AbiParser::default(),
             abi_cache: HashMap::new(),
@@ -103,7 +103,7 @@ impl CheatCodes {
         S::Error: Debug,
     {
         // staticcall to get the slot, where we force the return type as u256
-        self.inspector.reset_access_recording();
+        self.inspector.reset();
         let ret = self
             .caller
             .static_call(state, to, calldata.clone(), &mut self.inspector)
@@ -114,64 +114,58 @@ impl CheatCodes {
         }
         let cdata = cdata.unwrap();
 
-        // check the access
-        if let Some(ref accesses) = self.inspector.accesses {
-            // check whether it is a real staticcall
-            if !accesses.writes.is_empty() {
-                return None;
-            }
+        // check whether it is a real staticcall
+        if self.inspector.has_writes() {
+            return None;
+        }
 
-            // check read accesses
-            let raccesses = accesses.reads.get(&to)?.clone();
+        // check read accesses
+        let raccesses = self.inspector.reads(to);
+        if raccesses.is_empty() {
+            return None;
+        }
 
-            if raccesses.len() == 1 {
-                let slot = raccesses[0];
+        if raccesses.len() == 1 {
+            let slot = raccesses[0];
 
-                // sanity check
-                let rdata = state.storage(to, slot).ok()?;
-                if rdata == cdata {
-                    return Some(slot);
+            // sanity check
+            let rdata = state.storage(to, slot).ok()?;
+            if rdata == cdata {
+                return Some(slot);
+            }
+        } else {
+            // there are multiple reads, we need to check if the data is the same
+            let magic = U256::from(0xdeadbeefu64);
+            for slot in raccesses {
+                let prev = state.storage(to, slot).ok()?;
+                if cdata != prev.into() {
+                    continue;
                 }
-            } else {
-                // there are multiple reads, we need to check if the data is the same
-                let magic = U256::from(0xdeadbeefu64);
-                for slot in raccesses {
-                    let prev = state.storage(to, slot).ok()?;
-                    if cdata != prev.into() {
-                        continue;
-                    }
 
-                    // update the target slot
-                    state
-                        .insert_account_storage(to, slot, magic)
-                        .expect("insert should not fail");
-
-                    // we have to do another call to check if the slot is correct,
-                    // because changing the slot might change the program flow
-                    self.inspector.disable_access_recording();
-                    let ret = self
-                        .caller
-                        .static_call(
-                            state,
-                            to,
-                            calldata.clone(),
-                            &mut self.inspector,
-                        )
-                        .ok()?;
-                    let cdata = SolUint256::abi_decode(&ret, false);
-                    if cdata.is_err() {
-                        return None;
-                    }
-                    let cdata = cdata.unwrap();
+                // update the target slot
+                state
+                    .insert_account_storage(to, slot, magic)
+                    .expect("insert should not fail");
+
+                // we have to do another call to check if the slot is correct,
+                // because changing the slot might change the program flow
+                let ret = self
+                    .caller
+                    .static_call(state, to, calldata.clone(), no_inspector())
+                    .ok()?;
+                let cdata = SolUint256::abi_decode(&ret, false);
+                if cdata.is_err() {
+                    return None;
+                }
+                let cdata = cdata.unwrap();
 
-                    state
-                        .insert_account_storage(to, slot, prev)
-                        .expect("insert should not fail");
+                state
+                    .insert_account_storage(to, slot, prev)
+                    .expect("insert should not fail");
 
-                    if magic == cdata {
-                        // we got the slot!
-                        return Some(slot);
-                    }
+                if magic == cdata {
+                    // we got the slot!
+                    return Some(slot);
                 }
             }
         }
@@ -465,6 +459,83 @@ impl CheatCodes {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use alloy_dyn_abi::JsonAbiExt;
+    use alloy_json_abi::Function;
+    use alloy_sol_types::SolCall;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::{
+            inspector::no_inspector,
+            memory::MemoryBcState,
+            types::{Address, U256},
+        },
+    };
+    use libsofl_utils::solidity::{
+        caller::HighLevelCaller, scripting::deploy_contracts,
+    };
+
+    use crate::addressbook::ERC20ABI;
+
+    use super::CheatCodes;
+
+    #[test]
+    fn test_revert_cheat_writes_to_snapshot() {
+        let mut state = MemoryBcState::fresh();
+        let token = deploy_contracts(
+            &mut state,
+            "0.8.12",
+            r#"
+        contract Token {
+            mapping(address => uint256) public balanceOf;
+            function transfer(address to, uint256 value) public {
+                balanceOf[msg.sender] -= value;
+                balanceOf[to] += value;
+            }
+        }
+        "#,
+            vec!["Token"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let alice: Address = 0xa.cvt();
+        let bob: Address = 0xb.cvt();
+        let mut cheatcodes = CheatCodes::new();
+        let balance_of = ERC20ABI::balanceOfCall { owner: alice }.abi_encode();
+
+        let id = state.snapshot();
+        cheatcodes
+            .cheat_write(&mut state, token, balance_of.cvt(), U256::from(100))
+            .unwrap();
+        cheatcodes
+            .set_balance(&mut state, alice, U256::from(1))
+            .unwrap();
+        let transfer = Function::parse("transfer(address,uint256)")
+            .unwrap()
+            .abi_encode_input(&[bob.into(), U256::from(40).into()])
+            .unwrap();
+        HighLevelCaller::new(alice)
+            .bypass_check()
+            .call(&mut state, token, transfer.cvt(), None, no_inspector())
+            .unwrap();
+        let mut balance = |state: &mut _, account| {
+            cheatcodes.get_erc20_balance(state, token, account).unwrap()
+        };
+        assert_eq!(balance(&mut state, alice), U256::from(60));
+        assert_eq!(balance(&mut state, bob), U256::from(40));
+
+        assert!(state.revert_to(id));
+        assert_eq!(balance(&mut state, alice), U256::ZERO);
+        assert_eq!(balance(&mut state, bob), U256::ZERO);
+        assert_eq!(
+            cheatcodes.get_balance(&mut state, alice).unwrap(),
+            U256::ZERO
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests_with_dep {
     use crate::test::get_test_bc_provider;
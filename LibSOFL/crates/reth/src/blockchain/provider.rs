@@ -16,8 +16,8 @@ use libsofl_core::{
         state::BcState,
         transition::TransitionSpecBuilder,
         types::{
-            BlockEnv, BlockHash, BlockHashOrNumber, BlockNumber, CfgEnv, TxEnv,
-            TxHashOrPosition,
+            BlockEnv, BlockHash, BlockHashOrNumber, BlockNumber, CfgEnv,
+            SpecId, TxEnv, TxHashOrPosition,
         },
     },
     error::SoflError,
@@ -28,7 +28,7 @@ use reth_blockchain_tree::{
 };
 use reth_db::{open_db_read_only, DatabaseEnv};
 use reth_primitives::revm::env::fill_tx_env;
-use reth_primitives::ChainSpecBuilder;
+use reth_primitives::{ChainSpec, ChainSpecBuilder};
 pub use reth_provider::{
     providers::BlockchainProvider, BlockHashReader, BlockNumReader,
     BlockchainTreePendingStateProvider, ChainSpecProvider, EvmEnvProvider,
@@ -56,11 +56,21 @@ lazy_static! {
 #[derive(Clone)]
 pub struct RethProvider {
     pub bp: RethBlockchainProvider,
+    /// Spec used for all blocks instead of the one of the chain.
+    pub spec_id: Option<SpecId>,
 }
 
 impl RethProvider {
     pub fn from_db(datadir: &Path) -> Result<Self, SoflError> {
         let chain_spec = Arc::new(ChainSpecBuilder::mainnet().build());
+        Self::from_db_with_chain(datadir, chain_spec)
+    }
+
+    /// Open the db of a chain other than mainnet, e.g., a testnet.
+    pub fn from_db_with_chain(
+        datadir: &Path,
+        chain_spec: Arc<ChainSpec>,
+    ) -> Result<Self, SoflError> {
         let consensus = Arc::new(BeaconConsensus::new(chain_spec.clone()));
 
         let mut db_cache = DB_CACHE.lock().unwrap();
@@ -106,7 +116,13 @@ impl RethProvider {
                     e
                 ))
             })?;
-        Ok(Self { bp })
+        Ok(Self { bp, spec_id: None })
+    }
+
+    /// Execute all blocks with the given spec.
+    pub fn with_spec_id(mut self, spec_id: SpecId) -> Self {
+        self.spec_id = Some(spec_id);
+        self
     }
 }
 
@@ -221,7 +237,11 @@ impl BcProvider<RethTx> for RethProvider {
     ) -> Result<(), SoflError> {
         self.bp.fill_cfg_env_at(env, block.cvt()).map_err(|e| {
             SoflError::Provider(format!("failed to fill cfg env: {}", e))
-        })
+        })?;
+        if let Some(spec_id) = self.spec_id {
+            env.spec_id = spec_id;
+        }
+        Ok(())
     }
 
     fn fill_block_env(
@@ -1,20 +1,62 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
-use libsofl_core::error::SoflError;
+use libsofl_core::{blockchain::chain::parse_spec_id, error::SoflError};
 use libsofl_utils::{config::Config, log::info};
+use reth_primitives::{ChainSpec, ChainSpecBuilder, Genesis, HOLESKY, SEPOLIA};
 
 use crate::blockchain::provider::RethProvider;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RethConfig {
     pub datadir: String,
+    /// `mainnet`, `sepolia`, `holesky` or the path of a genesis JSON file,
+    /// mainnet if not set.
+    #[serde(default)]
+    pub chain: Option<String>,
+    /// Hardfork to execute all blocks with, e.g., `london`.
+    #[serde(default)]
+    pub spec_id: Option<String>,
 }
 
 impl RethConfig {
     pub fn bc_provider(&self) -> Result<RethProvider, SoflError> {
         info!("loading bc provider with reth db from {}", self.datadir);
         let datadir = Path::new(&self.datadir);
-        RethProvider::from_db(datadir)
+        let mut provider =
+            RethProvider::from_db_with_chain(datadir, self.chain_spec()?)?;
+        if let Some(spec_id) = &self.spec_id {
+            provider = provider.with_spec_id(parse_spec_id(spec_id)?);
+        }
+        Ok(provider)
+    }
+
+    fn chain_spec(&self) -> Result<Arc<ChainSpec>, SoflError> {
+        let Some(chain) = &self.chain else {
+            return Ok(Arc::new(ChainSpecBuilder::mainnet().build()));
+        };
+        match chain.to_lowercase().as_str() {
+            "mainnet" => Ok(Arc::new(ChainSpecBuilder::mainnet().build())),
+            "sepolia" => Ok(SEPOLIA.clone()),
+            "holesky" => Ok(HOLESKY.clone()),
+            _ => {
+                let json = std::fs::read_to_string(chain).map_err(|e| {
+                    SoflError::Config(format!(
+                        "failed to read genesis file {}: {}",
+                        chain, e
+                    ))
+                })?;
+                // reth's chain spec, unlike `ChainConfig::from_genesis_json`, activates
+                // the merge once the terminal total difficulty is reached
+                let genesis: Genesis =
+                    serde_json::from_str(&json).map_err(|e| {
+                        SoflError::Config(format!(
+                            "invalid genesis json: {}",
+                            e
+                        ))
+                    })?;
+                Ok(Arc::new(genesis.into()))
+            }
+        }
     }
 }
 
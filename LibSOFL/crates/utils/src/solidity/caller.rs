@@ -1,7 +1,6 @@
 pub use alloy_dyn_abi::{DynSolEvent, DynSolType};
 use alloy_dyn_abi::{DynSolValue, FunctionExt, JsonAbiExt};
 use alloy_json_abi::Function;
-use alloy_sol_types::{Revert, SolError};
 use libsofl_core::{
     blockchain::{provider::BcProvider, transaction::Tx},
     conversion::ConvertTo,
@@ -16,6 +15,9 @@ use libsofl_core::{
     },
     error::SoflError,
 };
+use tracing::debug;
+
+use super::revert::RevertReason;
 
 /// HighLevelCaller provider a high level interface for calling contract.
 /// HighLevelCaller is readonly caller, which means it can not change the state.
@@ -204,6 +206,8 @@ impl HighLevelCaller {
         }
     }
 
+    /// Call a contract with low-level calldata, committing the state changes.
+    /// The reason of a revert can be decoded from the returned error with `RevertData`.
     pub fn call<'a, BS: BcState, I: EvmInspector<&'a mut BS>>(
         &self,
         state: &'a mut BS,
@@ -236,8 +240,7 @@ impl HighLevelCaller {
                 gas_used: _,
                 output,
             } => {
-                let x = Revert::abi_decode(&output, false).unwrap();
-                println!("revert: {:?}", x.reason);
+                debug!(reason = %RevertReason::decode(&output), "call reverted");
                 Err(SoflError::Exec(result))
             }
             _ => Err(SoflError::Exec(result)),
@@ -280,8 +283,7 @@ impl HighLevelCaller {
                 gas_used: _,
                 output,
             } => {
-                let x = Revert::abi_decode(&output, false).unwrap();
-                println!("revert: {:?}", x.reason);
+                debug!(reason = %RevertReason::decode(&output), "call reverted");
                 Err(SoflError::Exec(result))
             }
             _ => Err(SoflError::Exec(result)),
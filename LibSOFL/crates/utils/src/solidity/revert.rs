@@ -0,0 +1,310 @@
+use std::{collections::HashMap, fmt};
+
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Error, JsonAbi};
+use alloy_sol_types::{Panic, Revert, SolError};
+use libsofl_core::{
+    engine::types::{Bytes, ExecutionResult, U256},
+    error::SoflError,
+};
+
+/// Reason of a revert, decoded from the revert data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RevertReason {
+    /// `Error(string)`, thrown by `revert("...")` and `require(..., "...")`.
+    Error(String),
+    /// `Panic(uint256)`, thrown by failed assertions, arithmetic overflows, etc.
+    Panic(U256),
+    /// A custom error known to the decoder.
+    Custom {
+        signature: String,
+        args: Vec<DynSolValue>,
+    },
+    /// No revert data, e.g., `revert()` or `require(...)` without a message.
+    Empty,
+    /// Revert data not decodable.
+    Unknown(Bytes),
+}
+
+impl RevertReason {
+    /// Decode the revert data as `Error(string)` or `Panic(uint256)`.
+    /// Use `RevertDecoder` to decode custom errors as well.
+    pub fn decode(data: &[u8]) -> Self {
+        if data.is_empty() {
+            return Self::Empty;
+        }
+        if data.starts_with(&Revert::SELECTOR) {
+            if let Ok(revert) = Revert::abi_decode(data, false) {
+                return Self::Error(revert.reason);
+            }
+        }
+        if data.starts_with(&Panic::SELECTOR) {
+            if let Ok(panic) = Panic::abi_decode(data, false) {
+                return Self::Panic(panic.code);
+            }
+        }
+        Self::Unknown(Bytes::copy_from_slice(data))
+    }
+
+    /// Description of a panic code, as documented by Solidity.
+    pub fn panic_description(code: U256) -> Option<&'static str> {
+        let description = match code.saturating_to::<u64>() {
+            0x00 => "generic compiler inserted panic",
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division or modulo by zero",
+            0x21 => "invalid enum value",
+            0x22 => "invalid storage byte array encoding",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to zero-initialized internal function",
+            _ => return None,
+        };
+        Some(description)
+    }
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(reason) => write!(f, "{}", reason),
+            Self::Panic(code) => match Self::panic_description(*code) {
+                Some(description) => write!(
+                    f,
+                    "panic {:#04x}: {}",
+                    code.to::<u64>(),
+                    description
+                ),
+                None => write!(f, "panic {}", code),
+            },
+            Self::Custom { signature, args } => {
+                let name = signature.split('(').next().unwrap_or_default();
+                let args: Vec<_> = args.iter().map(fmt_value).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            Self::Empty => write!(f, "empty revert data"),
+            Self::Unknown(data) => write!(f, "unknown revert data {}", data),
+        }
+    }
+}
+
+#[allow(unreachable_patterns)]
+fn fmt_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::Address(a) => a.to_string(),
+        DynSolValue::FixedBytes(word, size) => {
+            Bytes::copy_from_slice(&word[..*size]).to_string()
+        }
+        DynSolValue::Bytes(b) => Bytes::copy_from_slice(b).to_string(),
+        DynSolValue::String(s) => format!("{:?}", s),
+        DynSolValue::Array(vs) | DynSolValue::FixedArray(vs) => {
+            let vs: Vec<_> = vs.iter().map(fmt_value).collect();
+            format!("[{}]", vs.join(", "))
+        }
+        DynSolValue::Tuple(vs) => {
+            let vs: Vec<_> = vs.iter().map(fmt_value).collect();
+            format!("({})", vs.join(", "))
+        }
+        _ => format!("{:?}", value),
+    }
+}
+
+/// Decoder of revert data with known custom errors.
+#[derive(Debug, Clone, Default)]
+pub struct RevertDecoder {
+    errors: HashMap<[u8; 4], Vec<Error>>,
+}
+
+impl RevertDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a custom error, ignoring duplicated signatures.
+    pub fn add_error(&mut self, error: Error) -> &mut Self {
+        let candidates = self.errors.entry(error.selector().0).or_default();
+        let signature = error.signature();
+        if !candidates.iter().any(|e| e.signature() == signature) {
+            candidates.push(error);
+        }
+        self
+    }
+
+    /// Add a custom error by its signature, e.g., `InsufficientBalance(uint256,uint256)`.
+    pub fn add_signature(
+        &mut self,
+        signature: &str,
+    ) -> Result<&mut Self, SoflError> {
+        let error = Error::parse(signature).map_err(|e| {
+            SoflError::Abi(format!("invalid error {}: {}", signature, e))
+        })?;
+        Ok(self.add_error(error))
+    }
+
+    /// Add the custom errors in an ABI.
+    pub fn add_abi(&mut self, abi: &JsonAbi) -> &mut Self {
+        for error in abi.errors() {
+            self.add_error(error.clone());
+        }
+        self
+    }
+
+    /// Decode the revert data as `Error(string)`, `Panic(uint256)`,
+    /// or the first known custom error accepting the data.
+    pub fn decode(&self, data: &[u8]) -> RevertReason {
+        let reason = RevertReason::decode(data);
+        let RevertReason::Unknown(_) = reason else {
+            return reason;
+        };
+        let Some(selector) = data.get(..4) else {
+            return reason;
+        };
+        let candidates = self
+            .errors
+            .get(selector)
+            .map(|es| es.as_slice())
+            .unwrap_or_default();
+        candidates
+            .iter()
+            .find_map(|error| {
+                let args = error.abi_decode_input(&data[4..], false).ok()?;
+                Some(RevertReason::Custom {
+                    signature: error.signature(),
+                    args,
+                })
+            })
+            .unwrap_or(reason)
+    }
+}
+
+/// Access to the revert data of execution results and errors.
+pub trait RevertData {
+    /// Revert data, `None` if the execution does not revert.
+    fn revert_data(&self) -> Option<&Bytes>;
+
+    /// Revert reason decoded as `Error(string)` or `Panic(uint256)`,
+    /// `None` if the execution does not revert.
+    fn revert_reason(&self) -> Option<RevertReason> {
+        self.revert_data().map(|data| RevertReason::decode(data))
+    }
+
+    /// Revert reason decoded with custom errors,
+    /// `None` if the execution does not revert.
+    fn revert_reason_with(
+        &self,
+        decoder: &RevertDecoder,
+    ) -> Option<RevertReason> {
+        self.revert_data().map(|data| decoder.decode(data))
+    }
+}
+
+impl RevertData for ExecutionResult {
+    fn revert_data(&self) -> Option<&Bytes> {
+        match self {
+            ExecutionResult::Revert { output, .. } => Some(output),
+            _ => None,
+        }
+    }
+}
+
+impl RevertData for SoflError {
+    fn revert_data(&self) -> Option<&Bytes> {
+        match self {
+            SoflError::Exec(result) => result.revert_data(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+    use alloy_json_abi::Function;
+    use libsofl_core::{
+        conversion::ConvertTo,
+        engine::{
+            inspector::no_inspector,
+            memory::MemoryBcState,
+            types::{Address, U256},
+        },
+    };
+
+    use crate::solidity::{
+        caller::HighLevelCaller, scripting::deploy_contracts,
+    };
+
+    use super::{RevertData, RevertDecoder, RevertReason};
+
+    #[test]
+    fn test_decode_revert_reasons() {
+        let mut state = MemoryBcState::fresh();
+        let contract = deploy_contracts(
+            &mut state,
+            "0.8.12",
+            r#"
+        contract Reverter {
+            error Insufficient(address account, uint256 needed);
+            function message() public pure {
+                require(false, "not allowed");
+            }
+            function overflow(uint256 x) public pure returns (uint256) {
+                return x + 1;
+            }
+            function custom() public view {
+                revert Insufficient(msg.sender, 42);
+            }
+            function empty() public pure {
+                revert();
+            }
+        }
+        "#,
+            vec!["Reverter"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+
+        let mut call = |f: &str, args: &[DynSolValue]| {
+            let input =
+                Function::parse(f).unwrap().abi_encode_input(args).unwrap();
+            HighLevelCaller::default()
+                .bypass_check()
+                .call(&mut state, contract, input.cvt(), None, no_inspector())
+                .unwrap_err()
+        };
+
+        let err = call("message()", &[]);
+        assert_eq!(
+            err.revert_reason(),
+            Some(RevertReason::Error("not allowed".to_string()))
+        );
+        let err = call("overflow(uint256)", &[U256::MAX.into()]);
+        let reason = err.revert_reason().unwrap();
+        assert_eq!(reason, RevertReason::Panic(U256::from(0x11)));
+        assert_eq!(
+            reason.to_string(),
+            "panic 0x11: arithmetic overflow or underflow"
+        );
+        assert_eq!(
+            call("empty()", &[]).revert_reason(),
+            Some(RevertReason::Empty)
+        );
+
+        let err = call("custom()", &[]);
+        assert!(matches!(
+            err.revert_reason(),
+            Some(RevertReason::Unknown(_))
+        ));
+        let mut decoder = RevertDecoder::new();
+        decoder
+            .add_signature("Insufficient(address,uint256)")
+            .unwrap();
+        let reason = err.revert_reason_with(&decoder).unwrap();
+        let caller: Address = HighLevelCaller::default().address;
+        assert_eq!(reason.to_string(), format!("Insufficient({}, 42)", caller));
+    }
+}
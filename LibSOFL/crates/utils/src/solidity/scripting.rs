@@ -260,9 +260,13 @@ mod tests {
     use libsofl_core::{
         conversion::ConvertTo,
         engine::{
-            inspector::no_inspector,
+            inspector::{no_inspector, NoInspector},
             memory::MemoryBcState,
-            types::{Address, Database},
+            state::{BcState, BcStateRef},
+            transition::TransitionSpecBuilder,
+            types::{
+                Address, Database, ExecutionResult, TransactTo, TxEnv, U256,
+            },
         },
     };
 
@@ -393,6 +397,69 @@ mod tests {
         assert_eq!(ret, "first");
     }
 
+    #[test]
+    fn test_simulate_bundle() {
+        let mut state = MemoryBcState::fresh();
+        let counter = deploy_contracts(
+            &mut state,
+            "0.8.12",
+            r#"
+                contract Counter {
+                    uint256 public count;
+                    function inc() public returns (uint256) {
+                        count += 1;
+                        return count;
+                    }
+                }
+            "#,
+            vec!["Counter"],
+            Default::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let mut builder = TransitionSpecBuilder::default().bypass_check();
+        for _ in 0..3 {
+            let mut tx = TxEnv::default();
+            tx.transact_to = TransactTo::Call(counter);
+            tx.data = Function::parse("inc()")
+                .unwrap()
+                .abi_encode_input(&[])
+                .unwrap()
+                .into();
+            tx.gas_limit = 1_000_000;
+            builder = builder.append_tx_env(tx);
+        }
+        let spec = builder.build();
+        let counts = |results: &[ExecutionResult]| -> Vec<usize> {
+            results
+                .iter()
+                .map(|r| {
+                    let ret = sol_data::Uint::<256>::abi_decode(
+                        r.output().unwrap(),
+                        true,
+                    )
+                    .unwrap();
+                    ConvertTo::<usize>::cvt(&ret)
+                })
+                .collect()
+        };
+
+        // every transaction of a plain simulation sees the original state
+        let (_, results) =
+            state.simulate(spec.clone(), no_inspector()).unwrap();
+        assert_eq!(counts(&results), vec![1, 1, 1]);
+
+        let mut inspector = NoInspector {};
+        let (changes, results) =
+            state.simulate_bundle(spec, &mut inspector).unwrap();
+        assert_eq!(counts(&results), vec![1, 2, 3]);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(state.storage(counter, U256::ZERO).unwrap(), U256::ZERO);
+
+        state.apply_changes(changes);
+        assert_eq!(state.storage(counter, U256::ZERO).unwrap(), U256::from(3));
+    }
+
     #[test]
     fn test_run_yul() {
         let mut state = MemoryBcState::fresh();
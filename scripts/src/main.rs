@@ -1,314 +1,547 @@
-extern crate libsofl_core;
-extern crate libsofl_utils;
-extern crate libsofl_reth;
-extern crate libsofl_knowledge_index;
-extern crate reth_provider;
-extern crate serde_json;
-extern crate serde;
-
-use std::{collections::HashSet, sync::Arc};
-use std::i128;
-use std::convert::TryFrom;
+//! Replay transactions on a reth archive node and write their traces
+//! (money flow, function calls, and optionally opcodes, storage accesses and logs)
+//! into a dataset in `out_dir`.
+//!
+//! Hashes of the transactions written into the dataset or filtered out by `--address`
+//! are appended to `<out_dir>/progress.txt`, and these transactions are skipped,
+//! so an interrupted run can be resumed with the same addresses.
 
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::Parser;
 use libsofl_core::{
     blockchain::{
         provider::{BcProvider, BcStateProvider},
         transaction::Tx,
-        tx_position::TxPosition,
     },
     conversion::ConvertTo,
     engine::{
-        inspector::no_inspector, state::BcState,
-        transition::TransitionSpec, types::TxHash, types::BlockNumber,
+        state::BcState,
+        state_diff::StateDiff,
+        transition::TransitionSpec,
+        types::{Address, TxHash},
     },
+    error::SoflError,
 };
-
-use libsofl_reth::blockchain::provider::RethProvider;
-use libsofl_reth::blockchain::provider::RethBlockchainProvider;
-use libsofl_reth::config::RethConfig;
-use libsofl_utils::config::Config;
-use reth_provider::ReceiptProvider;
-
-use libsofl_knowledge_index::inspectors::{
-    extract_creation::ExtractCreationInspector,
-    extract_invocation::ExtractInvocationInspector,
-    extract_money_flow::ExtractMoneyFlowinspector,
-    extract_mf_and_fc::ExtractMFAndFCinspector,
-    extract_mf_and_fc_and_op::ExtractMFAndFCAndOPinspector,
+use libsofl_knowledge_index::{
+    block_tracer::{BlockTrace, BlockTracer},
+    dataset::{DatasetFormat, DatasetWriter},
+    inspectors::tx_tracer::{TraceConfig, TxTracer},
+    selectors::SelectorDb,
+    trace::{RevertedFlows, StepDetail, TxTrace},
+    valuation::Valuator,
 };
+use libsofl_reth::{blockchain::provider::RethProvider, config::RethConfig};
+use libsofl_utils::log::{error, info, warn};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Arg {
+    #[arg(
+        long,
+        env = "SOFL_RETH_DATADIR",
+        help = "data directory of the reth archive node"
+    )]
+    datadir: String,
+
+    #[arg(
+        long,
+        env = "SOFL_RETH_CHAIN",
+        help = "mainnet (default), sepolia, holesky or the path of a genesis JSON file"
+    )]
+    chain: Option<String>,
+
+    #[arg(short, long, default_value = "info")]
+    level: String,
+
+    #[arg(short, long, default_value = "8")]
+    jobs: usize,
+
+    #[arg(
+        short,
+        long,
+        help = "file of transaction hashes separated by newlines or commas, `-` for stdin"
+    )]
+    txs: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        value_parser = parse_block_range,
+        help = "block range FROM..TO (exclusive) or a single block, can be repeated"
+    )]
+    blocks: Vec<Range<u64>>,
+
+    #[arg(
+        short,
+        long,
+        help = "only trace transactions sent from or to this address, can be repeated"
+    )]
+    address: Vec<Address>,
+
+    #[arg(short, long, default_value = ".")]
+    out_dir: PathBuf,
+
+    #[arg(
+        short,
+        long,
+        default_value = "json",
+        value_parser = |s: &str| s.parse::<DatasetFormat>().map_err(|e| e.to_string()),
+        help = "output format: json (one file per transaction), jsonl, csv (money flow edges) or parquet"
+    )]
+    format: DatasetFormat,
+
+    #[arg(
+        long,
+        default_value = "100",
+        help = "number of transactions written between two flushes of the dataset"
+    )]
+    flush_every: usize,
+
+    #[arg(long, help = "trace opcodes")]
+    opcodes: bool,
+
+    #[arg(
+        long,
+        help = "trace rich steps with this number of stack words, implies --opcodes"
+    )]
+    rich_steps: Option<usize>,
+
+    #[arg(long, help = "trace storage accesses")]
+    storage: bool,
+
+    #[arg(long, help = "trace logs")]
+    logs: bool,
+
+    #[arg(
+        long,
+        help = "keep money flows inside reverted calls and mark them as reverted"
+    )]
+    mark_reverted: bool,
+
+    #[arg(
+        long,
+        help = "annotate money flows with their values in wei, priced at the state before the transaction"
+    )]
+    value: bool,
 
-use std::fs;
-use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-
-// index, from_addr, to_addr, token_addr, amount, tokenid, call_stack
-// usize, String, String, String, String, String, Vec<usize>
-#[derive(Default, Clone, Debug, Serialize)]
-pub struct MoneyFlow {
-    pub money_flow_index: usize,
-    pub from: String,
-    pub to: String,
-    pub token: String,
-    pub amount: String,
-    pub tokenid: String, // if nft token, this field is not empty
-    pub call_stack: Vec<usize>,
+    #[arg(
+        long,
+        help = "decode function calls with the ABIs in libsofl-periphery"
+    )]
+    decode: bool,
+
+    #[arg(
+        long,
+        help = "file of function signatures (e.g., a 4byte directory dump) to decode calls with, implies --decode"
+    )]
+    selectors: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "record the state diff (in the diff mode of geth's prestateTracer)"
+    )]
+    state_diff: bool,
 }
 
+impl Arg {
+    fn trace_config(&self) -> TraceConfig {
+        TraceConfig {
+            opcodes: self.opcodes || self.rich_steps.is_some(),
+            step_detail: match self.rich_steps {
+                Some(stack_top) => StepDetail::Rich { stack_top },
+                None => StepDetail::Basic,
+            },
+            storage: self.storage,
+            logs: self.logs,
+            reverted_flows: if self.mark_reverted {
+                RevertedFlows::Mark
+            } else {
+                RevertedFlows::Drop
+            },
+            ..Default::default()
+        }
+    }
 
-// #[derive(Default, Clone, Debug, Serialize)]
-// pub struct TxInfo {
-//     pub moneys: Vec;
-//     pub function_calls: Vec<Trace>;
-// }
-
-fn replay_tx(hash: &str) {
-    // intialization
-    let cfg = RethConfig::must_load();
-    let bp = cfg.bc_provider().unwrap();
-    let tx_hash_type: TxHash = hash.cvt();
-    let tx_hash_str = hash.cvt();
-    let spec = TransitionSpec::from_tx_hash(&bp, tx_hash_type).unwrap();
-    let tx = bp.tx(tx_hash_str).unwrap();
-    let mut state = bp.bc_state_at(tx.position().unwrap()).unwrap();
-
-    // // use a ExtractCreationInspector
-    // let mut creation_insp = ExtractCreationInspector::default();
-    // let r = state.transit(spec, &mut creation_insp).unwrap().pop().unwrap();
-    // println!("{} {}", r.is_success(), r.logs().len());
-    // let creations: Vec<(TxHash, String, bool)> = creation_insp
-    //             .created
-    //             .iter()
-    //             .map(|(addr, destruct)| {
-    //                 (tx_hash_type.clone(), ConvertTo::<String>::cvt(addr), *destruct)
-    //             })
-    //             .collect();
-    // println!("{:?}", creations);
-
-
-    // // use a ExtractCreationInspector
-    // let mut invocation_insp = ExtractInvocationInspector::default();
-    // let r = state.transit(spec, &mut invocation_insp).unwrap().pop().unwrap();
-    // println!("{} {}", r.is_success(), r.logs().len());
-    // let invocations: Vec<String> = invocation_insp
-    //             .invocations
-    //             .iter()
-    //             .map(|addr| ConvertTo::<String>::cvt(addr))
-    //             .collect();
-    // println!("{:?}", invocations);
-
-    // // use a ExtractMoneyFlowinspector
-    // let mut moneyflow_insp = ExtractMoneyFlowinspector::default();
-    // let r = state.transit(spec, &mut moneyflow_insp).unwrap().pop().unwrap();
-    // println!("{} {}", r.is_success(), r.logs().len());
-    // let moneys: Vec<(u32, String, String, String, String, String)> = moneyflow_insp
-    //                 .moneys
-    //                 .iter()
-    //                 .map(|(index, from_addr, to_addr, token_addr, amount, tokenid)|{
-    //                     (*index, 
-    //                     if (&from_addr).len() < usize::try_from(66).unwrap() {
-    //                         from_addr.clone().to_lowercase()
-    //                     } else {
-    //                         format!("0x{}", &from_addr.clone().to_lowercase()[26..66])
-    //                     },
-    //                     if (&to_addr).len() < usize::try_from(66).unwrap() {
-    //                         to_addr.clone().to_lowercase()
-    //                     } else {
-    //                         format!("0x{}", &to_addr.clone().to_lowercase()[26..66])
-    //                     },
-    //                     token_addr.clone().to_lowercase(), 
-    //                     if token_addr == "ETH" {
-    //                         amount.clone()
-    //                     } else {
-    //                         if amount == "" {
-    //                             // erc1155
-    //                             // println!("erc1155 {} {} {}", &tokenid.clone().len(), &tokenid.clone()[0..66], &tokenid.clone()[67..130]);
-    //                             i128::from_str_radix(&tokenid.clone()[67..130], 16).unwrap().to_string()
-    //                         } else {
-    //                             i128::from_str_radix(amount.clone().trim_start_matches("0x"), 16).unwrap().to_string()
-    //                         }
-    //                     },
-    //                     if amount == "" {
-    //                         // erc1155
-    //                         "0x".to_string() + tokenid.clone()[2..66].to_string().trim_start_matches('0')
-    //                     } else {
-    //                         tokenid.clone()
-    //                     })
-    //                 })
-    //                 .collect();
-    // println!("{:?}", moneys);
-    
-
-    // // use ExtractMFAndFCinspector
-    // let mut mf_and_fc_insp = ExtractMFAndFCinspector::default();
-    // let r = state.transit(spec, &mut mf_and_fc_insp).unwrap().pop().unwrap();
-    // println!("{} {}", r.is_success(), r.logs().len());
-    // let moneys: Vec<(usize, String, String, String, String, String, Vec<usize>)> = mf_and_fc_insp
-    //                 .moneys
-    //                 .iter()
-    //                 .map(|(index, from_addr, to_addr, token_addr, amount, tokenid, call_stack)|{
-    //                     (*index, 
-    //                     if (&from_addr).len() < usize::try_from(66).unwrap() {
-    //                         from_addr.clone().to_lowercase()
-    //                     } else {
-    //                         format!("0x{}", &from_addr.clone().to_lowercase()[26..66])
-    //                     },
-    //                     if (&to_addr).len() < usize::try_from(66).unwrap() {
-    //                         to_addr.clone().to_lowercase()
-    //                     } else {
-    //                         format!("0x{}", &to_addr.clone().to_lowercase()[26..66])
-    //                     },
-    //                     token_addr.clone().to_lowercase(), 
-    //                     if token_addr == "ETH" {
-    //                         amount.clone()
-    //                     } else {
-    //                         if amount == "" {
-    //                             // erc1155
-    //                             // println!("erc1155 {} {} {}", &tokenid.clone().len(), &tokenid.clone()[0..66], &tokenid.clone()[67..130]);
-    //                             i128::from_str_radix(&tokenid.clone()[67..130], 16).unwrap().to_string()
-    //                         } else {
-    //                             i128::from_str_radix(amount.clone().trim_start_matches("0x"), 16).unwrap().to_string()
-    //                         }
-    //                     },
-    //                     if amount == "" {
-    //                         // erc1155
-    //                         "0x".to_string() + tokenid.clone()[2..66].to_string().trim_start_matches('0')
-    //                     } else {
-    //                         tokenid.clone()
-    //                     },
-    //                     call_stack.clone())
-    //                 })
-    //                 .collect();
-    // println!("{:?}", moneys);
-    // println!("{:?}", mf_and_fc_insp.traces);
-
-    // use ExtractMFAndFCAndOPinspector
-    let mut mf_and_fc_and_op_insp = ExtractMFAndFCAndOPinspector::default();
-    let r = state.transit(spec, &mut mf_and_fc_and_op_insp).unwrap().pop().unwrap();
-    let moneys: Vec<(usize, String, String, String, String, String, Vec<usize>)> = mf_and_fc_and_op_insp
-                    .moneys
-                    .iter()
-                    .map(|(index, from_addr, to_addr, token_addr, amount, tokenid, call_stack)|{
-                        (*index, 
-                        if (&from_addr).len() < usize::try_from(66).unwrap() {
-                            from_addr.clone().to_lowercase()
-                        } else {
-                            format!("0x{}", &from_addr.clone().to_lowercase()[26..66])
-                        },
-                        if (&to_addr).len() < usize::try_from(66).unwrap() {
-                            to_addr.clone().to_lowercase()
-                        } else {
-                            format!("0x{}", &to_addr.clone().to_lowercase()[26..66])
-                        },
-                        token_addr.clone().to_lowercase(), 
-                        if token_addr == "ETH" {
-                            amount.clone()
-                        } else {
-                            if amount == "" {
-                                // erc1155
-                                // println!("erc1155 {} {} {}", &tokenid.clone().len(), &tokenid.clone()[0..66], &tokenid.clone()[67..130]);
-                                i128::from_str_radix(&tokenid.clone()[67..130], 16).unwrap().to_string()
-                            } else {
-                                i128::from_str_radix(amount.clone().trim_start_matches("0x"), 16).unwrap().to_string()
-                            }
-                        },
-                        if amount == "" {
-                            // erc1155
-                            "0x".to_string() + tokenid.clone()[2..66].to_string().trim_start_matches('0')
-                        } else {
-                            tokenid.clone()
-                        },
-                        call_stack.clone())
-                    })
-                    .collect();
-                        
-    // write it into a file
-    let file_path = format!("{}.txt", hash);
-    if let Err(e) = fs::remove_file(&file_path) {
-        if e.kind() != std::io::ErrorKind::NotFound {
-            eprintln!("Error removing {}: {}", file_path, e);
+    fn selector_db(&self) -> Option<SelectorDb> {
+        if !self.decode && self.selectors.is_none() {
+            return None;
+        }
+        let mut db = SelectorDb::with_periphery_abis();
+        if let Some(path) = &self.selectors {
+            let added = db
+                .load_signatures(path)
+                .expect("failed to load function signatures");
+            info!(added, "function signatures loaded");
         }
+        Some(db)
     }
-    let json_string1 = serde_json::to_string(&moneys).expect("Failed to serialize data to JSON");
-    let json_string2 = serde_json::to_string(&mf_and_fc_and_op_insp.traces).expect("Failed to serialize data to JSON");
-    match fs::write(file_path.clone(), format!("{} \n {}", json_string1,json_string2)) {
-        Ok(()) => println!("Result has been written to {}", file_path.clone()),
-        Err(e) => eprintln!("Error writing to {}: {}", file_path.clone(), e),
+}
+
+fn parse_block_range(s: &str) -> Result<Range<u64>, String> {
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u64>()
+            .map_err(|e| format!("invalid block number {}: {}", n, e))
+    };
+    match s.split_once("..") {
+        Some((from, to)) => Ok(parse(from)?..parse(to)?),
+        None => parse(s).map(|bn| bn..bn + 1),
+    }
+}
+
+/// Read transaction hashes separated by newlines, commas or whitespaces.
+/// Tokens not starting with `0x` are ignored.
+fn read_tx_hashes(source: &str) -> io::Result<Vec<TxHash>> {
+    let reader: Box<dyn BufRead> = if source == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(fs::File::open(source)?))
+    };
+    let mut hashes = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let tokens = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| t.starts_with("0x"));
+        for token in tokens {
+            match token.parse::<TxHash>() {
+                Ok(hash) => hashes.push(hash),
+                Err(_) => warn!(token, "skip invalid transaction hash"),
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+/// Hashes of the transactions already written into the dataset or filtered out.
+struct Progress {
+    path: PathBuf,
+    done: HashSet<TxHash>,
+}
+
+impl Progress {
+    fn load(out_dir: &Path) -> io::Result<Self> {
+        let path = out_dir.join("progress.txt");
+        let done = if path.exists() {
+            read_tx_hashes(path.to_str().expect("invalid path"))?
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        Ok(Self { path, done })
+    }
+
+    fn record(&mut self, hashes: Vec<TxHash>) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        for hash in hashes {
+            writeln!(file, "{}", hash)?;
+            self.done.insert(hash);
+        }
+        file.sync_all()
     }
 }
 
-// fn main() -> io::Result<()> {
-//     let mut index = 1;
-//     // get the transactions and execute one by one
-//     let file = File::open("./attack_transaction.txt")?;
-//     let reader = io::BufReader::new(file);
-
-//     // Create a vector to store the strings starting with "0x"
-//     let mut tx_vector: Vec<String> = Vec::new();
-
-//     // Iterate over each line in the file
-//     for line in reader.lines() {
-//         if let Ok(tx_list) = line {
-//             // Split the line based on ","
-//             let txs: Vec<&str> = tx_list.split(',').collect();
-//             // Iterate over each item in the split list
-//             for tx in txs {
-//                 // Trim whitespace and check if the item starts with "0x"
-//                 if let Some(tx_str) = tx.trim().strip_prefix("0x") {
-//                     // Add the string to the vector
-//                     replay_tx(&format!("0x{}", tx_str));
-//                     tx_vector.push(format!("0x{}", tx_str));
-//                     println!("{} {}", index, format!("0x{}", tx_str));
-//                     index += 1;
-//                 }
-//             }
-//         }
-//     }
-//     Ok(())
-// }
-
-
-// // get normal tranasctions information
-// fn main() {
-//     // first attack transaction in block 18476513
-//     // second attack transaction in block 18523344
-//     // let's get the middle 3 blocks: 18477000; 18478000; 18479000
-//     let cfg = RethConfig::must_load();
-//     let bp = cfg.bc_provider().unwrap();
-//     let txs = bp.txs_in_block(18479000u64.cvt()).unwrap();
-//     let mut index = 1;
-//     for tx in txs {
-//         replay_tx(&tx.hash().to_string());
-//         println!("{} 18479000 {}", index, tx.hash().to_string());
-//         index += 1
-//     }
-// }
-
-
-fn main() {
-    // test ExtractMoneyFlowinspector
-    // erc20 and suicide and eth transfer from call
-    // let hash1 = "0x32c83905db61047834f29385ff8ce8cb6f3d24f97e24e6101d8301619efee96e";
-    // // erc777
-    // let hash2 = "0x855b19e03d7a87b0f925f0ebdced4669f086cfd12fd242bc5a7044c7707d3842"; 
-    // // erc721
-    // let hash3 = "0x0cd617a3cc204b159c2a88cf64b559a46d8b9c93cd3c2d7abe7adcbb132a73d8";
-    // // create
-    // let hash4 = "0x0d8fc644c8178a352cd39aed1c3ae58434e422390c96f182b3fd68c53ec070e2";
-    // // // error: erc11155 tokenid is too big to convert
-    // // erc11155 single
-    // let hash5 = "0xd61e017c1fdc7b9464eccf880db17b1c66ff87cca9def4c5de0b62f9b05c4c56";
-    // // erc11155 batch
-    // let hash6 = "0x8f09040707aeaa5bf1c3e98f8c867a2b416c1b3bbac10d19b7f0de8f51b15dba";
-    // // erc4626: it seems this type of token emits Transfer()
-    // let hash7 = "0x79f41f29b5f637012ca435e11b07cfc2e71b073698a792e30b0b1b6d3c7890d3";
-    // replay_tx(hash7);
-
-    // // test ExtractMFAndFCinspector
-    // replay_tx(hash1);
-
-    // test ExtractMFAndFCAndOPinspector
-    let hash1 = "0x32c83905db61047834f29385ff8ce8cb6f3d24f97e24e6101d8301619efee96e";
-    replay_tx(hash1);
-}
\ No newline at end of file
+/// A unit of tracing work.
+#[derive(Clone, Copy, Debug)]
+enum Job {
+    /// A transaction, replayed on the state before it.
+    Tx(TxHash),
+    /// All transactions of a block, replayed once from the state before the block.
+    Block(u64),
+}
+
+/// Whether the transaction is sent from or to one of the addresses, if any.
+fn is_selected(
+    addresses: &HashSet<Address>,
+    from: Address,
+    to: Option<Address>,
+) -> bool {
+    addresses.is_empty()
+        || addresses.contains(&from)
+        || to.is_some_and(|to| addresses.contains(&to))
+}
+
+/// Trace a transaction, or return `None` if it is filtered out.
+fn trace_tx(
+    provider: &RethProvider,
+    hash: TxHash,
+    addresses: &HashSet<Address>,
+    config: TraceConfig,
+    valuator: Option<&Valuator>,
+    selectors: Option<Arc<SelectorDb>>,
+    state_diff: bool,
+) -> Result<Option<TxTrace>, SoflError> {
+    let tx = provider.tx(hash.into())?;
+    if !is_selected(addresses, tx.sender(), tx.to()) {
+        return Ok(None);
+    }
+    let position = tx.position().ok_or(SoflError::NotFound(format!(
+        "transaction with hash {}",
+        hash
+    )))?;
+
+    let spec = TransitionSpec::from_tx_hash(provider, hash)?;
+    let block = spec.block.number.saturating_to();
+    let mut state = provider.bc_state_at(position)?;
+    let mut tracer = TxTracer::new(config);
+    if let Some(selectors) = selectors {
+        tracer = tracer.with_selectors(selectors);
+    }
+    // the state is not modified by the simulation, so that it is still
+    // the state before the transaction when computing the diff and prices
+    let (mut changes, _) = state.simulate(spec, &mut tracer)?;
+    let diff = if state_diff {
+        let changes = changes.pop().unwrap_or_default();
+        Some(StateDiff::new(&mut state, &changes)?)
+    } else {
+        None
+    };
+    let mut trace = tracer.into_trace();
+    trace.state_diff = diff;
+    if let Some(meta) = trace.meta.as_mut() {
+        meta.hash = Some(hash);
+        meta.block = Some(block);
+    }
+    if let Some(valuator) = valuator {
+        valuator.annotate_flows(&mut state, position, &mut trace.money_flows);
+    }
+    Ok(Some(trace))
+}
+
+/// Trace all transactions of a block, with `None` for those filtered out.
+fn trace_block(
+    provider: &RethProvider,
+    block: u64,
+    addresses: &HashSet<Address>,
+    config: TraceConfig,
+    valuator: Option<&Valuator>,
+    selectors: Option<Arc<SelectorDb>>,
+    state_diff: bool,
+) -> Result<Vec<(TxHash, Option<TxTrace>)>, SoflError> {
+    let mut tracer = BlockTracer::new(config).with_state_diff(state_diff);
+    if let Some(selectors) = selectors {
+        tracer = tracer.with_selectors(selectors);
+    }
+    if let Some(valuator) = valuator {
+        tracer = tracer.with_valuator(valuator.clone());
+    }
+    let BlockTrace { summary, traces } = tracer.trace_block(provider, block)?;
+    info!(
+        block,
+        txs = summary.txs,
+        failed = summary.failed,
+        gas_used = summary.gas_used,
+        money_flows = summary.money_flows,
+        "block traced"
+    );
+
+    let mut results = Vec::with_capacity(traces.len());
+    for trace in traces {
+        let meta = trace.meta.as_ref().expect("bug: block trace without meta");
+        let hash = meta.hash.expect("bug: block trace without hash");
+        if !is_selected(addresses, meta.caller, meta.to) {
+            results.push((hash, None));
+            continue;
+        }
+        results.push((hash, Some(trace)));
+    }
+    Ok(results)
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Arg::parse();
+
+    let log_filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&args.level))
+        .expect("failed to create console logger filter");
+    tracing_subscriber::fmt()
+        .with_env_filter(log_filter)
+        .with_target(false)
+        .init();
+
+    let cfg = RethConfig {
+        datadir: args.datadir.clone(),
+        chain: args.chain.clone(),
+        spec_id: None,
+    };
+    let provider = Arc::new(cfg.bc_provider().unwrap());
+    info!(datadir = cfg.datadir, "reth blockchain provider connected");
+    let mut writer = args
+        .format
+        .open(&args.out_dir)
+        .expect("failed to open dataset");
+    let mut progress =
+        Progress::load(&args.out_dir).expect("failed to load progress");
+
+    // collect transactions, and trace whole blocks at once
+    let hashes = match &args.txs {
+        Some(source) => {
+            read_tx_hashes(source).expect("failed to read transaction hashes")
+        }
+        None => Vec::new(),
+    };
+    let (mut todo, mut done) = (0, 0);
+    let mut jobs = Vec::new();
+    for hash in hashes {
+        if progress.done.contains(&hash) {
+            done += 1;
+        } else {
+            todo += 1;
+            jobs.push(Job::Tx(hash));
+        }
+    }
+    for range in &args.blocks {
+        for bn in range.clone() {
+            match provider.txs_in_block(bn.cvt()) {
+                Ok(txs) => {
+                    let remaining = txs
+                        .iter()
+                        .filter(|tx| !progress.done.contains(&tx.hash()))
+                        .count();
+                    done += txs.len() - remaining;
+                    if remaining > 0 {
+                        todo += remaining;
+                        jobs.push(Job::Block(bn));
+                    }
+                }
+                Err(e) => error!(block = bn, err = %e, "failed to get block"),
+            }
+        }
+    }
+    info!(
+        txs = todo,
+        done,
+        format = %args.format,
+        "start tracing transactions"
+    );
+
+    // handle signals
+    let cancellation_token = CancellationToken::new();
+    let cancel = cancellation_token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("received signal, stopping...");
+            cancel.cancel();
+        }
+    });
+
+    let addresses: Arc<HashSet<Address>> =
+        Arc::new(args.address.iter().copied().collect());
+    let config = args.trace_config();
+    let valuator = args.value.then(Valuator::new);
+    let selectors = args.selector_db().map(Arc::new);
+    let state_diff = args.state_diff;
+    let (mut traced, mut filtered, mut failed) = (0, 0, 0);
+    let mut unflushed = Vec::new();
+    let mut tasks = JoinSet::new();
+    let mut pending = jobs.into_iter();
+    loop {
+        // keep at most `jobs` transactions or blocks in flight
+        while tasks.len() < args.jobs && !cancellation_token.is_cancelled() {
+            let Some(job) = pending.next() else {
+                break;
+            };
+            let provider = provider.clone();
+            let addresses = addresses.clone();
+            let valuator = valuator.clone();
+            let selectors = selectors.clone();
+            tasks.spawn_blocking(move || {
+                let r = match job {
+                    Job::Tx(hash) => trace_tx(
+                        &provider,
+                        hash,
+                        &addresses,
+                        config,
+                        valuator.as_ref(),
+                        selectors,
+                        state_diff,
+                    )
+                    .map(|trace| vec![(hash, trace)]),
+                    Job::Block(block) => trace_block(
+                        &provider,
+                        block,
+                        &addresses,
+                        config,
+                        valuator.as_ref(),
+                        selectors,
+                        state_diff,
+                    ),
+                };
+                (job, r)
+            });
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        match joined.expect("tracing task panicked") {
+            (_, Ok(traces)) => {
+                for (hash, trace) in traces {
+                    // done before the run, or in another job
+                    if progress.done.contains(&hash)
+                        || unflushed.contains(&hash)
+                    {
+                        continue;
+                    }
+                    // filtered transactions are done as well, so that
+                    // their blocks are not traced again when resuming
+                    let Some(trace) = trace else {
+                        filtered += 1;
+                        unflushed.push(hash);
+                        continue;
+                    };
+                    match writer.write(hash, &trace) {
+                        Ok(()) => {
+                            info!(tx = %hash, "transaction traced");
+                            traced += 1;
+                            unflushed.push(hash);
+                        }
+                        Err(e) => {
+                            error!(tx = %hash, err = %e, "failed to write trace");
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+            (job, Err(e)) => {
+                error!(job = ?job, err = %e, "failed to trace");
+                failed += 1;
+            }
+        }
+        if unflushed.len() >= args.flush_every {
+            flush(&mut writer, &mut progress, &mut unflushed);
+        }
+    }
+    flush(&mut writer, &mut progress, &mut unflushed);
+    if cancellation_token.is_cancelled() {
+        warn!(remaining = pending.len(), "tracing interrupted");
+    }
+    info!(traced, filtered, failed, "tracing finished");
+}
+
+/// Flush the dataset and record the flushed and filtered transactions as done.
+fn flush(
+    writer: &mut Box<dyn DatasetWriter>,
+    progress: &mut Progress,
+    unflushed: &mut Vec<TxHash>,
+) {
+    writer.flush().expect("failed to flush dataset");
+    progress
+        .record(std::mem::take(unflushed))
+        .expect("failed to record progress");
+}